@@ -0,0 +1,389 @@
+//! INSPIRE ATOM download service feeds, such as
+//! <https://service.pdok.nl/kadaster/ad/atom/index.xml>.
+//!
+//! A service feed has an entry per dataset, linking to the dataset feed. A dataset feed has an
+//! entry per CRS and format the dataset is offered in, linking to the files to download.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDateTime;
+use quick_xml::{
+    events::{BytesStart, Event},
+    name::{QName, ResolveResult},
+    reader::NsReader,
+};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::{
+    error::{Error, ParseErrorKind, Result},
+    parser::{line_breaks, parse_date_time},
+};
+
+macro_rules! atom {
+    ($name:literal) => {
+        concat!("{http://www.w3.org/2005/Atom}", $name)
+    };
+}
+
+macro_rules! inspire_dls {
+    ($name:literal) => {
+        concat!(
+            "{http://inspire.ec.europa.eu/schemas/inspire_dls/1.0}",
+            $name
+        )
+    };
+}
+
+#[derive(Default)]
+pub struct Feed {
+    pub title: Option<String>,
+    pub updated: Option<NaiveDateTime>,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Default)]
+pub struct Entry {
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub updated: Option<NaiveDateTime>,
+    /// The `inspire_dls:spatial_dataset_identifier_code` of the dataset.
+    pub dataset_identifier: Option<String>,
+    /// The coordinate reference systems the entry is offered in.
+    pub crs: Vec<Crs>,
+    pub links: Vec<Link>,
+}
+
+/// A coordinate reference system, from an entry's `category`.
+pub struct Crs {
+    /// E.g. `http://www.opengis.net/def/crs/EPSG/0/28992`.
+    pub uri: String,
+    /// E.g. `Amersfoort / RD New`.
+    pub label: Option<String>,
+}
+
+pub struct Link {
+    pub href: String,
+    pub rel: Option<String>,
+    /// The media type, which is the format of a download, e.g. `application/zip`.
+    pub media_type: Option<String>,
+    /// The size of a download in bytes.
+    pub length: Option<u64>,
+    pub title: Option<String>,
+}
+
+const ATOM_MEDIA_TYPE: &str = "application/atom+xml";
+
+impl Link {
+    fn is_atom(&self) -> bool {
+        self.media_type
+            .as_deref()
+            .is_some_and(|media_type| media_type.starts_with(ATOM_MEDIA_TYPE))
+    }
+
+    fn is_content(&self) -> bool {
+        matches!(
+            self.rel.as_deref(),
+            None | Some("alternate" | "enclosure" | "section")
+        )
+    }
+}
+
+impl Entry {
+    /// Returns the files this entry offers for download, if it is a dataset feed entry.
+    pub fn downloads(&self) -> impl Iterator<Item = &Link> {
+        self.links
+            .iter()
+            .filter(|link| link.is_content() && !link.is_atom())
+    }
+
+    /// Returns the link to the dataset feed, if this is a service feed entry.
+    pub fn dataset_feed(&self) -> Option<&Link> {
+        self.links
+            .iter()
+            .find(|link| link.is_content() && link.is_atom())
+    }
+}
+
+impl Feed {
+    /// Reads a feed, keeping the hrefs of its links as written; see [`Feed::resolve_links`].
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Feed> {
+        let mut xml_reader = NsReader::from_reader(reader);
+        let mut buf = Vec::new();
+        let mut path: Vec<String> = Vec::new();
+        let mut text = String::new();
+        let mut feed = Feed::default();
+        // The line the next event starts on
+        let mut line = 1;
+
+        loop {
+            buf.clear();
+            let xml_error = move |xml_reader: &NsReader<R>, source| Error::Xml {
+                position: xml_reader.error_position(),
+                line,
+                source,
+            };
+            let event = xml_reader
+                .read_event_into(&mut buf)
+                .map_err(|source| xml_error(&xml_reader, source))?;
+            let event_line = line;
+            line += line_breaks(&event);
+            match event {
+                Event::Start(e) => {
+                    path.push(clark_name(&xml_reader, e.name()));
+                    text.clear();
+                    feed.visit_start(&path, &xml_reader, &e);
+                }
+                Event::Empty(e) => {
+                    path.push(clark_name(&xml_reader, e.name()));
+                    feed.visit_start(&path, &xml_reader, &e);
+                    path.pop();
+                }
+                Event::Text(e) => {
+                    let unescaped = e
+                        .unescape()
+                        .map_err(|source| xml_error(&xml_reader, source))?;
+                    text.push_str(&unescaped);
+                }
+                Event::CData(e) => text.push_str(&String::from_utf8_lossy(&e)),
+                Event::End(_) => {
+                    feed.visit_end(&path, text.trim())
+                        .map_err(|kind| Error::Parse {
+                            position: xml_reader.buffer_position(),
+                            line: event_line,
+                            path: path.join("/"),
+                            kind,
+                        })?;
+                    path.pop();
+                    text.clear();
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(feed)
+    }
+
+    /// Resolves relative hrefs of the links against `base_url`, the URL the feed was read from.
+    /// Hrefs that can't be resolved are kept as written.
+    pub fn resolve_links(&mut self, base_url: &str) {
+        let Ok(base_url) = Url::parse(base_url) else {
+            return;
+        };
+        for link in self.entries.iter_mut().flat_map(|entry| &mut entry.links) {
+            if let Ok(url) = base_url.join(&link.href) {
+                link.href = url.into();
+            }
+        }
+    }
+
+    fn visit_start<R>(&mut self, path: &[String], xml_reader: &NsReader<R>, e: &BytesStart) {
+        let path: Vec<&str> = path.iter().map(String::as_str).collect();
+        match path.as_slice() {
+            [atom!("feed"), atom!("entry")] => self.entries.push(Entry::default()),
+            [atom!("feed"), atom!("entry"), atom!("link")] => {
+                let attribute = |name| attribute(xml_reader, e, name);
+                if let (Some(entry), Some(href)) = (self.entries.last_mut(), attribute("href")) {
+                    entry.links.push(Link {
+                        href,
+                        rel: attribute("rel"),
+                        media_type: attribute("type"),
+                        length: attribute("length").and_then(|length| length.parse().ok()),
+                        title: attribute("title"),
+                    });
+                }
+            }
+            [atom!("feed"), atom!("entry"), atom!("category")] => {
+                if let (Some(entry), Some(uri)) =
+                    (self.entries.last_mut(), attribute(xml_reader, e, "term"))
+                {
+                    entry.crs.push(Crs {
+                        uri,
+                        label: attribute(xml_reader, e, "label"),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn visit_end(&mut self, path: &[String], text: &str) -> Result<(), ParseErrorKind> {
+        let path: Vec<&str> = path.iter().map(String::as_str).collect();
+        let entry = self.entries.last_mut();
+        match (path.as_slice(), entry) {
+            ([atom!("feed"), atom!("title")], _) => self.title = Some(text.to_string()),
+            ([atom!("feed"), atom!("updated")], _) => self.updated = Some(parse_date_time(text)?),
+            ([atom!("feed"), atom!("entry"), atom!("id")], Some(entry)) => {
+                entry.id = Some(text.to_string())
+            }
+            ([atom!("feed"), atom!("entry"), atom!("title")], Some(entry)) => {
+                entry.title = Some(text.to_string())
+            }
+            ([atom!("feed"), atom!("entry"), atom!("updated")], Some(entry)) => {
+                entry.updated = Some(parse_date_time(text)?)
+            }
+            (
+                [
+                    atom!("feed"),
+                    atom!("entry"),
+                    inspire_dls!("spatial_dataset_identifier_code"),
+                ],
+                Some(entry),
+            ) => entry.dataset_identifier = Some(text.to_string()),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn clark_name<R>(xml_reader: &NsReader<R>, name: QName) -> String {
+    let (namespace, local_name) = xml_reader.resolve_element(name);
+    let local_name = String::from_utf8_lossy(local_name.as_ref());
+    match namespace {
+        ResolveResult::Bound(namespace) => {
+            format!(
+                "{{{}}}{}",
+                String::from_utf8_lossy(namespace.as_ref()),
+                local_name
+            )
+        }
+        _ => local_name.into_owned(),
+    }
+}
+
+/// Returns the unescaped value of an attribute without a namespace.
+fn attribute<R>(xml_reader: &NsReader<R>, e: &BytesStart, name: &str) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|attr| {
+            let (namespace, local_name) = xml_reader.resolve_attribute(attr.key);
+            matches!(namespace, ResolveResult::Unbound) && local_name.as_ref() == name.as_bytes()
+        })
+        .and_then(|attr| attr.unescape_value().ok())
+        .map(|value| value.into_owned())
+}
+
+/// Reads a feed from an `http://` or `https://` URL, or else from a local file.
+///
+/// The links of a feed read from a URL are resolved against the URL it was served from, after
+/// any redirects, so they can be passed to [`fetch`].
+pub fn read_feed(location: &str) -> Result<Feed> {
+    if location.starts_with("http://") || location.starts_with("https://") {
+        let response = ureq::get(location).call()?;
+        let url = response.get_url().to_string();
+        let mut feed = Feed::from_reader(BufReader::new(response.into_reader()))?;
+        feed.resolve_links(&url);
+        Ok(feed)
+    } else {
+        Feed::from_reader(BufReader::new(File::open(location)?))
+    }
+}
+
+/// Downloads `link` into `cache_dir`, returning the path of the downloaded file.
+///
+/// The download is checked against the length in the feed and against `sha256`, a hexadecimal
+/// SHA-256 checksum, if given. The checksum of every download is stored next to it, in a file
+/// with `.sha256` appended to its name, so that a file that is already in the cache is only used
+/// again if it still has that checksum (or `sha256`, if given).
+pub fn fetch(link: &Link, cache_dir: &Path, sha256: Option<&str>) -> Result<PathBuf> {
+    std::fs::create_dir_all(cache_dir)?;
+    let file_name = cache_file_name(&link.href);
+    let path = cache_dir.join(&file_name);
+    let checksum_path = cache_dir.join(format!("{}.sha256", file_name));
+
+    if path.exists() {
+        let expected = match sha256 {
+            Some(sha256) => Some(sha256.to_string()),
+            None => std::fs::read_to_string(&checksum_path)
+                .ok()
+                .map(|checksum| checksum.trim().to_string()),
+        };
+        if let Some(expected) = expected
+            && sha256_file(&path)?.eq_ignore_ascii_case(&expected)
+        {
+            return Ok(path);
+        }
+    }
+
+    let partial_path = cache_dir.join(format!("{}.part", file_name));
+    let result = download(link, &partial_path, sha256);
+    let checksum = match result {
+        Ok(checksum) => checksum,
+        Err(error) => {
+            let _ = std::fs::remove_file(&partial_path);
+            return Err(error);
+        }
+    };
+    std::fs::write(&checksum_path, format!("{}\n", checksum))?;
+    std::fs::rename(&partial_path, &path)?;
+    Ok(path)
+}
+
+/// Downloads `link` to `path`, returning the SHA-256 checksum of the download.
+fn download(link: &Link, path: &Path, sha256: Option<&str>) -> Result<String> {
+    let response = ureq::get(&link.href).call()?;
+    let mut reader = response.into_reader();
+    let mut writer = BufWriter::new(File::create(path)?);
+    let (length, checksum) = copy_and_hash(&mut reader, &mut writer)?;
+    writer.flush()?;
+
+    if let Some(expected) = link.length
+        && expected != length
+    {
+        return Err(Error::Verification {
+            url: link.href.clone(),
+            expected: format!("{} bytes", expected),
+            found: format!("{} bytes", length),
+        });
+    }
+    if let Some(expected) = sha256
+        && !checksum.eq_ignore_ascii_case(expected)
+    {
+        return Err(Error::Verification {
+            url: link.href.clone(),
+            expected: format!("SHA-256 {}", expected),
+            found: format!("SHA-256 {}", checksum),
+        });
+    }
+    Ok(checksum)
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let (_, checksum) = copy_and_hash(&mut File::open(path)?, &mut std::io::sink())?;
+    Ok(checksum)
+}
+
+/// Copies `reader` to `writer`, returning the number of bytes and their SHA-256 checksum.
+fn copy_and_hash(reader: &mut dyn Read, writer: &mut dyn Write) -> Result<(u64, String)> {
+    let mut hasher = Sha256::new();
+    let mut length = 0;
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let read = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error.into()),
+        };
+        hasher.update(&buf[..read]);
+        writer.write_all(&buf[..read])?;
+        length += read as u64;
+    }
+    Ok((length, format!("{:x}", hasher.finalize())))
+}
+
+/// Returns the last segment of the path of a URL, or `download` if it has none, prefixed with a
+/// hash of the whole URL, as different downloads often have the same file name.
+fn cache_file_name(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let name = path.rsplit('/').next().unwrap_or_default();
+    let name = if name.is_empty() || name == "." || name == ".." {
+        "download"
+    } else {
+        name
+    };
+    let hash = format!("{:x}", Sha256::digest(url));
+    format!("{}-{}", &hash[..16], name)
+}
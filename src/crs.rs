@@ -0,0 +1,154 @@
+//! Conversion between RD New (EPSG:28992) and WGS84 coordinates.
+//!
+//! This uses the polynomial approximation published by Schreutelkamp and Strang van Hees
+//! ("Benaderingsformules voor de transformatie tussen RD- en WGS84-kaartcoördinaten"), which is
+//! accurate to about a metre within the RD domain. It does not apply the RDNAPTRANS correction
+//! grid.
+
+use std::str::FromStr;
+
+/// RD coordinates of the Amersfoort reference point.
+const X0: f64 = 155_000.0;
+const Y0: f64 = 463_000.0;
+/// WGS84 latitude and longitude of the Amersfoort reference point.
+const PHI0: f64 = 52.155_174_40;
+const LAM0: f64 = 5.387_206_21;
+
+/// Coefficients `(p, q, K)` for the latitude in arc seconds: `sum(K * dx^p * dy^q)`.
+const K: [(i32, i32, f64); 11] = [
+    (0, 1, 3235.65389),
+    (2, 0, -32.58297),
+    (0, 2, -0.24750),
+    (2, 1, -0.84978),
+    (0, 3, -0.06550),
+    (2, 2, -0.01709),
+    (1, 0, -0.00738),
+    (4, 0, 0.00530),
+    (2, 3, -0.00039),
+    (4, 1, 0.00033),
+    (1, 1, -0.00012),
+];
+
+/// Coefficients `(p, q, L)` for the longitude in arc seconds: `sum(L * dx^p * dy^q)`.
+const L: [(i32, i32, f64); 12] = [
+    (1, 0, 5260.52916),
+    (1, 1, 105.94684),
+    (1, 2, 2.45656),
+    (3, 0, -0.81885),
+    (1, 3, 0.05594),
+    (3, 1, -0.05607),
+    (0, 1, 0.01199),
+    (3, 2, -0.00256),
+    (1, 4, 0.00128),
+    (0, 2, 0.00022),
+    (2, 0, -0.00022),
+    (5, 0, 0.00026),
+];
+
+/// Coefficients `(p, q, R)` for the RD x coordinate in metres: `sum(R * dphi^p * dlam^q)`.
+const R: [(i32, i32, f64); 9] = [
+    (0, 1, 190094.945),
+    (1, 1, -11832.228),
+    (2, 1, -114.221),
+    (0, 3, -32.391),
+    (1, 0, -0.705),
+    (3, 1, -2.340),
+    (1, 3, -0.608),
+    (0, 2, -0.008),
+    (2, 3, 0.148),
+];
+
+/// Coefficients `(p, q, S)` for the RD y coordinate in metres: `sum(S * dphi^p * dlam^q)`.
+const S: [(i32, i32, f64); 10] = [
+    (1, 0, 309056.544),
+    (0, 2, 3638.893),
+    (2, 0, 73.077),
+    (1, 2, -157.984),
+    (3, 0, 59.788),
+    (0, 1, 0.433),
+    (2, 2, -6.439),
+    (1, 1, -0.032),
+    (0, 4, 0.092),
+    (1, 4, -0.054),
+];
+
+fn polynomial(coefficients: &[(i32, i32, f64)], a: f64, b: f64) -> f64 {
+    coefficients
+        .iter()
+        .map(|&(p, q, c)| c * a.powi(p) * b.powi(q))
+        .sum()
+}
+
+/// Converts RD New coordinates in metres to a WGS84 `(latitude, longitude)` in degrees.
+pub fn rd_to_wgs84(x: f64, y: f64) -> (f64, f64) {
+    let dx = (x - X0) * 1e-5;
+    let dy = (y - Y0) * 1e-5;
+    let lat = PHI0 + polynomial(&K, dx, dy) / 3600.0;
+    let lon = LAM0 + polynomial(&L, dx, dy) / 3600.0;
+    (lat, lon)
+}
+
+/// Converts a WGS84 latitude and longitude in degrees to RD New `(x, y)` coordinates in metres.
+pub fn wgs84_to_rd(lat: f64, lon: f64) -> (f64, f64) {
+    let dphi = 0.36 * (lat - PHI0);
+    let dlam = 0.36 * (lon - LAM0);
+    let x = X0 + polynomial(&R, dphi, dlam);
+    let y = Y0 + polynomial(&S, dphi, dlam);
+    (x, y)
+}
+
+/// Which coordinate systems the emitters write address positions in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CoordinateOutput {
+    /// RD New `x` and `y`, as found in the dataset.
+    #[default]
+    Rd,
+    /// WGS84 `lat` and `lon`.
+    Wgs84,
+    Both,
+}
+
+impl CoordinateOutput {
+    pub fn rd(self) -> bool {
+        matches!(self, CoordinateOutput::Rd | CoordinateOutput::Both)
+    }
+
+    pub fn wgs84(self) -> bool {
+        matches!(self, CoordinateOutput::Wgs84 | CoordinateOutput::Both)
+    }
+
+    /// Names of the coordinate columns written for this output.
+    pub fn columns(self) -> &'static [&'static str] {
+        match self {
+            CoordinateOutput::Rd => &["x", "y"],
+            CoordinateOutput::Wgs84 => &["lat", "lon"],
+            CoordinateOutput::Both => &["x", "y", "lat", "lon"],
+        }
+    }
+
+    /// Converts an RD New position to the values for [`CoordinateOutput::columns`].
+    pub fn values(self, x: f64, y: f64) -> Vec<f64> {
+        let mut values = Vec::with_capacity(4);
+        if self.rd() {
+            values.extend([x, y]);
+        }
+        if self.wgs84() {
+            let (lat, lon) = rd_to_wgs84(x, y);
+            values.extend([lat, lon]);
+        }
+        values
+    }
+}
+
+impl FromStr for CoordinateOutput {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rd" => Ok(CoordinateOutput::Rd),
+            "wgs84" => Ok(CoordinateOutput::Wgs84),
+            "both" => Ok(CoordinateOutput::Both),
+            _ => Err(format!("Unknown coordinate system: {}", s)),
+        }
+    }
+}
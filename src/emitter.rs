@@ -1,19 +1,110 @@
 pub mod multifile;
 pub mod null;
+pub mod resolved;
 pub mod sqlite;
+pub mod validation;
 
-use crate::FeatureMember;
+use crate::{FeatureMember, Locator, Reference, error::Result, resolve::ResolvingEmitter};
 
 pub trait FeatureMemberEmitter {
-    fn emit(&mut self, feature_member: FeatureMember);
-    fn start(&mut self) {}
-    fn end(&mut self) {}
+    fn emit(&mut self, feature_member: FeatureMember<'_>) -> Result<()>;
+    fn start(&mut self) -> Result<()> {
+        Ok(())
+    }
+    fn end(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Pairs the parent address, building and untyped component references of an address with the
+/// name of the relation, as written to the `address_relations` table.
+pub(crate) fn address_relations(
+    parent_address_ref: Option<Reference>,
+    building_refs: Vec<Reference>,
+    other_component_refs: Vec<Reference>,
+) -> impl Iterator<Item = (&'static str, Reference)> {
+    parent_address_ref
+        .map(|reference| ("parent_address", reference))
+        .into_iter()
+        .chain(
+            building_refs
+                .into_iter()
+                .map(|reference| ("building", reference)),
+        )
+        .chain(
+            other_component_refs
+                .into_iter()
+                .map(|reference| ("component", reference)),
+        )
+}
+
+/// A row of the `address_locators` table: a designator, or a spelling of a locator name.
+pub(crate) struct LocatorRow<'b> {
+    /// The position of the locator among those of the address.
+    pub(crate) locator: usize,
+    pub(crate) level: Option<&'b str>,
+    /// `designator` or `name`.
+    pub(crate) kind: &'static str,
+    /// The `LocatorDesignatorTypeValue` or `LocatorNameTypeValue` code.
+    pub(crate) value_type: Option<&'b str>,
+    pub(crate) value: Option<&'b str>,
+}
+
+pub(crate) fn locator_rows<'b>(
+    locators: &'b [Locator<'_>],
+) -> impl Iterator<Item = LocatorRow<'b>> {
+    locators.iter().enumerate().flat_map(|(index, locator)| {
+        let level = locator.level.as_deref();
+        let designators = locator
+            .designators
+            .iter()
+            .map(move |designator| LocatorRow {
+                locator: index,
+                level,
+                kind: "designator",
+                value_type: designator.designator_type.as_deref(),
+                value: designator.designator.as_deref(),
+            });
+        let names = locator.names.iter().flat_map(move |name| {
+            name.names
+                .iter()
+                .flat_map(|name| &name.spellings)
+                .map(move |spelling| LocatorRow {
+                    locator: index,
+                    level,
+                    kind: "name",
+                    value_type: name.name_type.as_deref(),
+                    value: Some(&spelling.text),
+                })
+        });
+        designators.chain(names)
+    })
+}
+
+/// Writes a TSV field, escaping backslashes, tabs and line breaks with a backslash so that they
+/// can't split the field or the row.
+pub(crate) fn write_tsv_field(
+    writer: &mut impl std::io::Write,
+    field: &str,
+) -> std::io::Result<()> {
+    for byte in field.bytes() {
+        match byte {
+            b'\\' => writer.write_all(b"\\\\")?,
+            b'\t' => writer.write_all(b"\\t")?,
+            b'\n' => writer.write_all(b"\\n")?,
+            b'\r' => writer.write_all(b"\\r")?,
+            byte => writer.write_all(&[byte])?,
+        }
+    }
+    Ok(())
 }
 
 pub enum ChooseEmitter {
     MultiFile(multifile::MultiFileEmitter),
     Null(null::NullEmitter),
     Sqlite(sqlite::SqliteEmitter),
+    Resolved(ResolvingEmitter<resolved::ResolvedTsvEmitter>),
+    Validation(validation::ValidationEmitter),
 }
 
 impl From<multifile::MultiFileEmitter> for ChooseEmitter {
@@ -31,32 +122,48 @@ impl From<sqlite::SqliteEmitter> for ChooseEmitter {
         ChooseEmitter::Sqlite(emitter)
     }
 }
+impl From<ResolvingEmitter<resolved::ResolvedTsvEmitter>> for ChooseEmitter {
+    fn from(emitter: ResolvingEmitter<resolved::ResolvedTsvEmitter>) -> Self {
+        ChooseEmitter::Resolved(emitter)
+    }
+}
+impl From<validation::ValidationEmitter> for ChooseEmitter {
+    fn from(emitter: validation::ValidationEmitter) -> Self {
+        ChooseEmitter::Validation(emitter)
+    }
+}
 impl FeatureMemberEmitter for ChooseEmitter {
-    fn emit(&mut self, feature_member: FeatureMember) {
+    fn emit(&mut self, feature_member: FeatureMember<'_>) -> Result<()> {
         match self {
             ChooseEmitter::MultiFile(emitter) => emitter.emit(feature_member),
             ChooseEmitter::Null(emitter) => emitter.emit(feature_member),
             ChooseEmitter::Sqlite(emitter) => emitter.emit(feature_member),
+            ChooseEmitter::Resolved(emitter) => emitter.emit(feature_member),
+            ChooseEmitter::Validation(emitter) => emitter.emit(feature_member),
         }
     }
-    fn start(&mut self) {
+    fn start(&mut self) -> Result<()> {
         match self {
             ChooseEmitter::MultiFile(emitter) => emitter.start(),
             ChooseEmitter::Null(emitter) => emitter.start(),
             ChooseEmitter::Sqlite(emitter) => emitter.start(),
+            ChooseEmitter::Resolved(emitter) => emitter.start(),
+            ChooseEmitter::Validation(emitter) => emitter.start(),
         }
     }
-    fn end(&mut self) {
+    fn end(&mut self) -> Result<()> {
         match self {
             ChooseEmitter::MultiFile(emitter) => emitter.end(),
             ChooseEmitter::Null(emitter) => emitter.end(),
             ChooseEmitter::Sqlite(emitter) => emitter.end(),
+            ChooseEmitter::Resolved(emitter) => emitter.end(),
+            ChooseEmitter::Validation(emitter) => emitter.end(),
         }
     }
 }
 
 impl Default for ChooseEmitter {
     fn default() -> Self {
-        ChooseEmitter::Null(null::NullEmitter::default())
+        ChooseEmitter::Null(null::NullEmitter)
     }
 }
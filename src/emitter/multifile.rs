@@ -1,46 +1,118 @@
-use std::path::Path;
 use std::io::Write;
+use std::path::Path;
+
+use chrono::NaiveDateTime;
+
+use crate::{
+    FeatureMember, Identifier, Lifecycle, Reference, crs::CoordinateOutput, error::Result,
+};
+
+use super::{FeatureMemberEmitter, address_relations, locator_rows, write_tsv_field};
 
-use crate::FeatureMember;
+const LIFECYCLE_HEADER: &str =
+    "status\tvalid_from\tvalid_to\tbegin_lifespan_version\tend_lifespan_version";
+const IDENTIFIER_HEADER: &str = "version_id\tnamespace\tgml_id";
 
-use super::FeatureMemberEmitter;
+/// The life-cycle columns, in the order of [`LIFECYCLE_HEADER`].
+fn lifecycle_columns(lifecycle: &Lifecycle) -> [String; 5] {
+    let date_time = |date_time: Option<NaiveDateTime>| {
+        date_time
+            .map(|date_time| date_time.format("%Y-%m-%dT%H:%M:%S%.f").to_string())
+            .unwrap_or_default()
+    };
+    [
+        lifecycle.status.clone().unwrap_or_default(),
+        date_time(lifecycle.valid_from),
+        date_time(lifecycle.valid_to),
+        date_time(lifecycle.begin_lifespan_version),
+        date_time(lifecycle.end_lifespan_version),
+    ]
+}
+
+/// The identifier columns, in the order of [`IDENTIFIER_HEADER`].
+fn identifier_columns<'i>(identifier: &'i Identifier<'_>) -> [&'i str; 3] {
+    [
+        identifier.version_id.as_deref().unwrap_or_default(),
+        identifier.namespace.as_deref().unwrap_or_default(),
+        identifier.gml_id.as_deref().unwrap_or_default(),
+    ]
+}
+
+fn reference_column(reference: &Option<Reference>) -> &str {
+    reference
+        .as_ref()
+        .map_or("", |reference| reference.local_id.as_str())
+}
 
+/// Writes a row, escaping every field with [`write_tsv_field`].
+fn write_row<'f>(
+    writer: &mut impl Write,
+    fields: impl IntoIterator<Item = &'f str>,
+) -> std::io::Result<()> {
+    for (index, field) in fields.into_iter().enumerate() {
+        if index > 0 {
+            writer.write_all(b"\t")?;
+        }
+        write_tsv_field(writer, field)?;
+    }
+    writer.write_all(b"\n")
+}
+
+/// Writes every feature type to its own TSV file, with tabs, line breaks and backslashes in fields
+/// escaped by a backslash.
 pub struct MultiFileEmitter {
     address_writer: std::io::BufWriter<std::fs::File>,
     admin_unit_writer: std::io::BufWriter<std::fs::File>,
     address_area_writer: std::io::BufWriter<std::fs::File>,
     thoroughfare_writer: std::io::BufWriter<std::fs::File>,
+    postal_descriptor_writer: std::io::BufWriter<std::fs::File>,
+    address_relation_writer: std::io::BufWriter<std::fs::File>,
+    address_locator_writer: std::io::BufWriter<std::fs::File>,
+    coordinate_output: CoordinateOutput,
 }
 
 impl MultiFileEmitter {
-    pub fn new(base_path: &Path) -> Self {
+    pub fn new(base_path: &Path) -> Result<Self> {
         // Create separate files for each feature member type
-        let address_file = std::fs::File::create(base_path.join("addresses.tsv"))
-            .expect("Failed to create address file");
-        let admin_unit_file = std::fs::File::create(base_path.join("admin_units.tsv"))
-            .expect("Failed to create admin unit file");
-        let address_area_file = std::fs::File::create(base_path.join("address_areas.tsv"))
-            .expect("Failed to create address area file");
-        let thoroughfare_file = std::fs::File::create(base_path.join("thoroughfares.tsv"))
-            .expect("Failed to create thoroughfare file");
+        let address_file = std::fs::File::create(base_path.join("addresses.tsv"))?;
+        let admin_unit_file = std::fs::File::create(base_path.join("admin_units.tsv"))?;
+        let address_area_file = std::fs::File::create(base_path.join("address_areas.tsv"))?;
+        let thoroughfare_file = std::fs::File::create(base_path.join("thoroughfares.tsv"))?;
+        let postal_descriptor_file =
+            std::fs::File::create(base_path.join("postal_descriptors.tsv"))?;
+        let address_relation_file = std::fs::File::create(base_path.join("address_relations.tsv"))?;
+        let address_locator_file = std::fs::File::create(base_path.join("address_locators.tsv"))?;
 
         // Create buffered writers for each file
         let address_writer = std::io::BufWriter::new(address_file);
         let admin_unit_writer = std::io::BufWriter::new(admin_unit_file);
         let address_area_writer = std::io::BufWriter::new(address_area_file);
         let thoroughfare_writer = std::io::BufWriter::new(thoroughfare_file);
+        let postal_descriptor_writer = std::io::BufWriter::new(postal_descriptor_file);
+        let address_relation_writer = std::io::BufWriter::new(address_relation_file);
+        let address_locator_writer = std::io::BufWriter::new(address_locator_file);
 
-        MultiFileEmitter {
+        Ok(MultiFileEmitter {
             address_writer,
             admin_unit_writer,
             address_area_writer,
             thoroughfare_writer,
-        }
+            postal_descriptor_writer,
+            address_relation_writer,
+            address_locator_writer,
+            coordinate_output: CoordinateOutput::default(),
+        })
+    }
+
+    /// Sets the coordinate systems address positions are written in.
+    pub fn with_coordinate_output(mut self, coordinate_output: CoordinateOutput) -> Self {
+        self.coordinate_output = coordinate_output;
+        self
     }
 }
 
 impl FeatureMemberEmitter for MultiFileEmitter {
-    fn emit(&mut self, feature_member: FeatureMember) {
+    fn emit(&mut self, feature_member: FeatureMember<'_>) -> Result<()> {
         match feature_member {
             FeatureMember::Address {
                 local_id,
@@ -51,105 +123,222 @@ impl FeatureMemberEmitter for MultiFileEmitter {
                 admin_unit_ref,
                 address_area_ref,
                 thoroughfare_ref,
+                postal_descriptor_ref,
+                other_component_refs,
+                parent_address_ref,
+                building_refs,
+                locators,
+                position,
+                lifecycle,
+                identifier,
             } => {
+                for row in locator_rows(&locators) {
+                    let locator = row.locator.to_string();
+                    write_row(
+                        &mut self.address_locator_writer,
+                        [
+                            &*local_id,
+                            &locator,
+                            row.level.unwrap_or_default(),
+                            row.kind,
+                            row.value_type.unwrap_or_default(),
+                            row.value.unwrap_or_default(),
+                        ],
+                    )?;
+                }
+                for (relation, reference) in
+                    address_relations(parent_address_ref, building_refs, other_component_refs)
+                {
+                    write_row(
+                        &mut self.address_relation_writer,
+                        [&*local_id, relation, &reference.local_id, &reference.href],
+                    )?;
+                }
+
+                let coordinates = match &position {
+                    Some(position) => self
+                        .coordinate_output
+                        .values(position.x, position.y)
+                        .iter()
+                        .map(f64::to_string)
+                        .collect(),
+                    None => vec![String::new(); self.coordinate_output.columns().len()],
+                };
+                let position_default = position
+                    .as_ref()
+                    .map(|position| position.default.to_string())
+                    .unwrap_or_default();
+                let lifecycle = lifecycle_columns(&lifecycle);
                 // Write the address data to the address file
-                writeln!(
-                    self.address_writer,
-                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
-                    local_id,
-                    number.unwrap_or_default(),
-                    number_extension.unwrap_or_default(),
-                    number_2nd_extension.unwrap_or_default(),
-                    postal_delivery_identifier.unwrap_or_default(),
-                    admin_unit_ref.unwrap_or_default(),
-                    address_area_ref.unwrap_or_default(),
-                    thoroughfare_ref.unwrap_or_default()
-                )
-                .expect("Failed to write to address file");
+                let fields = [
+                    &*local_id,
+                    number.as_deref().unwrap_or_default(),
+                    number_extension.as_deref().unwrap_or_default(),
+                    number_2nd_extension.as_deref().unwrap_or_default(),
+                    postal_delivery_identifier.as_deref().unwrap_or_default(),
+                    reference_column(&admin_unit_ref),
+                    reference_column(&address_area_ref),
+                    reference_column(&thoroughfare_ref),
+                    reference_column(&postal_descriptor_ref),
+                ]
+                .into_iter()
+                .chain(coordinates.iter().map(String::as_str))
+                .chain([
+                    position
+                        .as_ref()
+                        .and_then(|position| position.method.as_deref())
+                        .unwrap_or_default(),
+                    position
+                        .as_ref()
+                        .and_then(|position| position.specification.as_deref())
+                        .unwrap_or_default(),
+                    &position_default,
+                ])
+                .chain(lifecycle.iter().map(String::as_str))
+                .chain(identifier_columns(&identifier));
+                write_row(&mut self.address_writer, fields)?;
             }
-            FeatureMember::AdminUnitName { local_id, name } => {
+            FeatureMember::AdminUnitName {
+                local_id,
+                name,
+                names: _,
+                lifecycle,
+                identifier,
+            } => {
                 // Write the admin unit name data to the admin unit file
-                writeln!(
-                    self.admin_unit_writer,
-                    "{}\t{}",
-                    local_id,
-                    name.unwrap_or_default()
-                )
-                .expect("Failed to write to admin unit file");
+                let lifecycle = lifecycle_columns(&lifecycle);
+                let fields = [&*local_id, name.as_deref().unwrap_or_default()]
+                    .into_iter()
+                    .chain(lifecycle.iter().map(String::as_str))
+                    .chain(identifier_columns(&identifier));
+                write_row(&mut self.admin_unit_writer, fields)?;
             }
             FeatureMember::AddressAreaName {
                 local_id,
                 name,
+                names: _,
                 situated_in_ref,
+                lifecycle,
+                identifier,
             } => {
                 // Write the address area name data to the address area file
-                writeln!(
-                    self.address_area_writer,
-                    "{}\t{}\t{}",
-                    local_id,
-                    name.unwrap_or_default(),
-                    situated_in_ref.unwrap_or_default()
-                )
-                .expect("Failed to write to address area file");
+                let lifecycle = lifecycle_columns(&lifecycle);
+                let fields = [
+                    &*local_id,
+                    name.as_deref().unwrap_or_default(),
+                    reference_column(&situated_in_ref),
+                ]
+                .into_iter()
+                .chain(lifecycle.iter().map(String::as_str))
+                .chain(identifier_columns(&identifier));
+                write_row(&mut self.address_area_writer, fields)?;
             }
             FeatureMember::ThoroughfareName {
                 local_id,
                 name,
+                names: _,
                 situated_in_ref,
+                lifecycle,
+                identifier,
             } => {
                 // Write the thoroughfare name data to the thoroughfare file
-                writeln!(
-                    self.thoroughfare_writer,
-                    "{}\t{}\t{}",
-                    local_id,
-                    name.unwrap_or_default(),
-                    situated_in_ref.unwrap_or_default()
-                )
-                .expect("Failed to write to thoroughfare file");
+                let lifecycle = lifecycle_columns(&lifecycle);
+                let fields = [
+                    &*local_id,
+                    name.as_deref().unwrap_or_default(),
+                    reference_column(&situated_in_ref),
+                ]
+                .into_iter()
+                .chain(lifecycle.iter().map(String::as_str))
+                .chain(identifier_columns(&identifier));
+                write_row(&mut self.thoroughfare_writer, fields)?;
+            }
+            FeatureMember::PostalDescriptor {
+                local_id,
+                post_code,
+                post_name,
+                admin_unit_ref,
+                lifecycle,
+                identifier,
+            } => {
+                // Write the postal descriptor data to the postal descriptor file
+                let lifecycle = lifecycle_columns(&lifecycle);
+                let fields = [
+                    &*local_id,
+                    post_code.as_deref().unwrap_or_default(),
+                    post_name.as_deref().unwrap_or_default(),
+                    reference_column(&admin_unit_ref),
+                ]
+                .into_iter()
+                .chain(lifecycle.iter().map(String::as_str))
+                .chain(identifier_columns(&identifier));
+                write_row(&mut self.postal_descriptor_writer, fields)?;
             }
         }
+        Ok(())
     }
 
-    fn start(&mut self) {
+    fn start(&mut self) -> Result<()> {
         // Write headers to each file
         writeln!(
             self.address_writer,
-            "local_id\tnumber\tnumber_extension\tnumber_2nd_extension\tpostal_delivery_identifier\tadmin_unit_ref\taddress_area_ref\tthoroughfare_ref"
-        ).expect("Failed to write address header");
-        self.address_writer
-            .flush()
-            .expect("Failed to flush address writer");
-
-        writeln!(self.admin_unit_writer, "local_id\tname").expect("Failed to write admin unit header");
-        self.admin_unit_writer
-            .flush()
-            .expect("Failed to flush admin unit writer");
-
-        writeln!(self.address_area_writer, "local_id\tname\tsituated_in_ref")
-            .expect("Failed to write address area header");
-        self.address_area_writer
-            .flush()
-            .expect("Failed to flush address area writer");
-
-        writeln!(self.thoroughfare_writer, "local_id\tname\tsituated_in_ref")
-            .expect("Failed to write thoroughfare header");
-        self.thoroughfare_writer
-            .flush()
-            .expect("Failed to flush thoroughfare writer");
+            "local_id\tnumber\tnumber_extension\tnumber_2nd_extension\tpostal_delivery_identifier\tadmin_unit_ref\taddress_area_ref\tthoroughfare_ref\tpostal_descriptor_ref\t{}\tposition_method\tposition_specification\tposition_default\t{}\t{}",
+            self.coordinate_output.columns().join("\t"),
+            LIFECYCLE_HEADER,
+            IDENTIFIER_HEADER
+        )?;
+        self.address_writer.flush()?;
+
+        writeln!(
+            self.admin_unit_writer,
+            "local_id\tname\t{}\t{}",
+            LIFECYCLE_HEADER, IDENTIFIER_HEADER
+        )?;
+        self.admin_unit_writer.flush()?;
+
+        writeln!(
+            self.address_area_writer,
+            "local_id\tname\tsituated_in_ref\t{}\t{}",
+            LIFECYCLE_HEADER, IDENTIFIER_HEADER
+        )?;
+        self.address_area_writer.flush()?;
+
+        writeln!(
+            self.thoroughfare_writer,
+            "local_id\tname\tsituated_in_ref\t{}\t{}",
+            LIFECYCLE_HEADER, IDENTIFIER_HEADER
+        )?;
+        self.thoroughfare_writer.flush()?;
+
+        writeln!(
+            self.postal_descriptor_writer,
+            "local_id\tpost_code\tpost_name\tadmin_unit_ref\t{}\t{}",
+            LIFECYCLE_HEADER, IDENTIFIER_HEADER
+        )?;
+        self.postal_descriptor_writer.flush()?;
+
+        writeln!(
+            self.address_relation_writer,
+            "address_local_id\trelation\tlocal_id\thref"
+        )?;
+        self.address_relation_writer.flush()?;
+
+        writeln!(
+            self.address_locator_writer,
+            "address_local_id\tlocator\tlevel\tkind\ttype\tvalue"
+        )?;
+        self.address_locator_writer.flush()?;
+        Ok(())
     }
 
-    fn end(&mut self) {
-        self.address_writer
-            .flush()
-            .expect("Failed to flush address writer");
-        self.admin_unit_writer
-            .flush()
-            .expect("Failed to flush admin unit writer");
-        self.address_area_writer
-            .flush()
-            .expect("Failed to flush address area writer");
-        self.thoroughfare_writer
-            .flush()
-            .expect("Failed to flush thoroughfare writer");
+    fn end(&mut self) -> Result<()> {
+        self.address_writer.flush()?;
+        self.admin_unit_writer.flush()?;
+        self.address_area_writer.flush()?;
+        self.thoroughfare_writer.flush()?;
+        self.postal_descriptor_writer.flush()?;
+        self.address_relation_writer.flush()?;
+        self.address_locator_writer.flush()?;
+        Ok(())
     }
 }
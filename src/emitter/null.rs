@@ -1,4 +1,4 @@
-use crate::FeatureMember;
+use crate::{FeatureMember, error::Result};
 
 use super::FeatureMemberEmitter;
 
@@ -16,15 +16,18 @@ impl Default for NullEmitter {
     }
 }
 impl FeatureMemberEmitter for NullEmitter {
-    fn emit(&mut self, _feature_member: FeatureMember) {
+    fn emit(&mut self, _feature_member: FeatureMember<'_>) -> Result<()> {
         // No operation
+        Ok(())
     }
 
-    fn start(&mut self) {
+    fn start(&mut self) -> Result<()> {
         // No operation
+        Ok(())
     }
 
-    fn end(&mut self) {
+    fn end(&mut self) -> Result<()> {
         // No operation
+        Ok(())
     }
 }
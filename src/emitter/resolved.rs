@@ -0,0 +1,59 @@
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::{
+    emitter::write_tsv_field,
+    error::Result,
+    resolve::{ResolvedAddress, ResolvedAddressEmitter},
+};
+
+/// Writes resolved addresses to `resolved_addresses.tsv`, with tabs, line breaks and backslashes
+/// in names escaped by a backslash.
+pub struct ResolvedTsvEmitter {
+    writer: BufWriter<std::fs::File>,
+}
+
+impl ResolvedTsvEmitter {
+    pub fn new(base_path: &Path) -> Result<Self> {
+        let file = std::fs::File::create(base_path.join("resolved_addresses.tsv"))?;
+        Ok(ResolvedTsvEmitter {
+            writer: BufWriter::new(file),
+        })
+    }
+}
+
+impl ResolvedAddressEmitter for ResolvedTsvEmitter {
+    fn emit(&mut self, address: ResolvedAddress) -> Result<()> {
+        let fields = [
+            Some(address.local_id),
+            address.street,
+            address.number,
+            address.number_extension,
+            address.number_2nd_extension,
+            address.postcode,
+            address.city,
+            address.municipality,
+        ];
+        for (index, field) in fields.into_iter().enumerate() {
+            if index > 0 {
+                self.writer.write_all(b"\t")?;
+            }
+            write_tsv_field(&mut self.writer, &field.unwrap_or_default())?;
+        }
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn start(&mut self) -> Result<()> {
+        writeln!(
+            self.writer,
+            "local_id\tstreet\tnumber\tnumber_extension\tnumber_2nd_extension\tpostcode\tcity\tmunicipality"
+        )?;
+        Ok(())
+    }
+
+    fn end(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
@@ -1,11 +1,15 @@
 use std::path::Path;
 
-use crate::FeatureMember;
+use rusqlite::ToSql;
 
-use super::FeatureMemberEmitter;
+use crate::{FeatureMember, GeographicalName, Reference, crs::CoordinateOutput, error::Result};
+
+use super::{FeatureMemberEmitter, LocatorRow, address_relations, locator_rows};
 
 pub struct SqliteEmitter {
     db: rusqlite::Connection,
+    coordinate_output: CoordinateOutput,
+    insert_address_sql: String,
 }
 
 impl SqliteEmitter {
@@ -18,56 +22,264 @@ impl SqliteEmitter {
         db.pragma_update(None, "cache_size", "10000")?;
         db.pragma_update(None, "locking_mode", "EXCLUSIVE")?;
 
-        Ok(SqliteEmitter { db })
+        let coordinate_output = CoordinateOutput::default();
+        Ok(SqliteEmitter {
+            db,
+            coordinate_output,
+            insert_address_sql: insert_address_sql(coordinate_output),
+        })
+    }
+
+    /// Sets the coordinate systems address positions are written in.
+    ///
+    /// This determines the columns of the `addresses` table, so it has to be set before calling
+    /// [`SqliteEmitter::create_tables`].
+    pub fn with_coordinate_output(mut self, coordinate_output: CoordinateOutput) -> Self {
+        self.coordinate_output = coordinate_output;
+        self.insert_address_sql = insert_address_sql(coordinate_output);
+        self
     }
 
+    /// Creates the tables, replacing any that exist, so that a database written before with other
+    /// coordinate columns, or by an older version, gets the current ones.
     pub fn create_tables(&self) -> rusqlite::Result<()> {
+        self.db.execute_batch(
+            "DROP TABLE IF EXISTS addresses;
+            DROP TABLE IF EXISTS admin_units;
+            DROP TABLE IF EXISTS address_areas;
+            DROP TABLE IF EXISTS thoroughfares;
+            DROP TABLE IF EXISTS postal_descriptors;
+            DROP TABLE IF EXISTS address_relations;
+            DROP TABLE IF EXISTS address_locators;
+            DROP TABLE IF EXISTS names;",
+        )?;
+
+        let coordinate_columns = self
+            .coordinate_output
+            .columns()
+            .iter()
+            .map(|column| format!("{} REAL,", column))
+            .collect::<Vec<_>>()
+            .join("\n                ");
         self.db.execute(
-            "CREATE TABLE IF NOT EXISTS addresses (
-                local_id INTEGER PRIMARY KEY,
+            &format!(
+                "CREATE TABLE addresses (
+                local_id TEXT PRIMARY KEY,
                 number TEXT,
                 number_extension TEXT,
                 number_2nd_extension TEXT,
                 postal_delivery_identifier TEXT,
                 admin_unit_ref TEXT,
                 address_area_ref TEXT,
-                thoroughfare_ref TEXT
+                thoroughfare_ref TEXT,
+                postal_descriptor_ref TEXT,
+                {}
+                position_method TEXT,
+                position_specification TEXT,
+                position_default INTEGER,
+                status TEXT,
+                valid_from TEXT,
+                valid_to TEXT,
+                begin_lifespan_version TEXT,
+                end_lifespan_version TEXT,
+                version_id TEXT,
+                namespace TEXT,
+                gml_id TEXT
             )",
+                coordinate_columns
+            ),
             (),
         )?;
 
         self.db.execute(
-            "CREATE TABLE IF NOT EXISTS admin_units (
-                local_id INTEGER PRIMARY KEY,
-                name TEXT
+            "CREATE TABLE admin_units (
+                local_id TEXT PRIMARY KEY,
+                name TEXT,
+                status TEXT,
+                valid_from TEXT,
+                valid_to TEXT,
+                begin_lifespan_version TEXT,
+                end_lifespan_version TEXT,
+                version_id TEXT,
+                namespace TEXT,
+                gml_id TEXT
             )",
             (),
         )?;
 
         self.db.execute(
-            "CREATE TABLE IF NOT EXISTS address_areas (
-                local_id INTEGER PRIMARY KEY,
+            "CREATE TABLE address_areas (
+                local_id TEXT PRIMARY KEY,
                 name TEXT,
-                situated_in_ref TEXT
+                situated_in_ref TEXT,
+                status TEXT,
+                valid_from TEXT,
+                valid_to TEXT,
+                begin_lifespan_version TEXT,
+                end_lifespan_version TEXT,
+                version_id TEXT,
+                namespace TEXT,
+                gml_id TEXT
             )",
             (),
         )?;
 
         self.db.execute(
-            "CREATE TABLE IF NOT EXISTS thoroughfares (
-                local_id INTEGER PRIMARY KEY,
+            "CREATE TABLE thoroughfares (
+                local_id TEXT PRIMARY KEY,
                 name TEXT,
-                situated_in_ref TEXT
+                situated_in_ref TEXT,
+                status TEXT,
+                valid_from TEXT,
+                valid_to TEXT,
+                begin_lifespan_version TEXT,
+                end_lifespan_version TEXT,
+                version_id TEXT,
+                namespace TEXT,
+                gml_id TEXT
             )",
             (),
         )?;
 
+        self.db.execute(
+            "CREATE TABLE postal_descriptors (
+                local_id TEXT PRIMARY KEY,
+                post_code TEXT,
+                post_name TEXT,
+                admin_unit_ref TEXT,
+                status TEXT,
+                valid_from TEXT,
+                valid_to TEXT,
+                begin_lifespan_version TEXT,
+                end_lifespan_version TEXT,
+                version_id TEXT,
+                namespace TEXT,
+                gml_id TEXT
+            )",
+            (),
+        )?;
+
+        self.db.execute(
+            "CREATE TABLE address_relations (
+                address_local_id TEXT NOT NULL,
+                relation TEXT NOT NULL,
+                local_id TEXT NOT NULL,
+                href TEXT NOT NULL
+            )",
+            (),
+        )?;
+        self.db.execute(
+            "CREATE INDEX address_relations_local_id
+                ON address_relations (local_id)",
+            (),
+        )?;
+
+        self.db.execute(
+            "CREATE TABLE address_locators (
+                address_local_id TEXT NOT NULL,
+                locator INTEGER NOT NULL,
+                level TEXT,
+                kind TEXT NOT NULL,
+                type TEXT,
+                value TEXT
+            )",
+            (),
+        )?;
+
+        self.db.execute(
+            "CREATE TABLE names (
+                feature_table TEXT NOT NULL,
+                local_id TEXT NOT NULL,
+                language TEXT,
+                name_status TEXT,
+                spelling TEXT NOT NULL,
+                script TEXT
+            )",
+            (),
+        )?;
+        self.db
+            .execute("CREATE INDEX names_spelling ON names (spelling)", ())?;
+
+        Ok(())
+    }
+
+    /// Inserts every spelling of the names of a feature in `feature_table` into `names`.
+    fn insert_names(
+        &self,
+        feature_table: &str,
+        local_id: &str,
+        names: &[GeographicalName],
+    ) -> rusqlite::Result<()> {
+        let mut statement = self.db.prepare_cached(
+            "INSERT INTO names (
+                feature_table, local_id, language, name_status, spelling, script
+            ) VALUES (?, ?, ?, ?, ?, ?)",
+        )?;
+        for name in names {
+            for spelling in &name.spellings {
+                statement.execute(rusqlite::params![
+                    feature_table,
+                    local_id,
+                    name.language,
+                    name.name_status,
+                    spelling.text,
+                    spelling.script
+                ])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Inserts the parent address, building and untyped component references of an address into
+    /// `address_relations`.
+    fn insert_address_relations(
+        &self,
+        local_id: &str,
+        relations: impl Iterator<Item = (&'static str, Reference)>,
+    ) -> rusqlite::Result<()> {
+        let mut statement = self.db.prepare_cached(
+            "INSERT INTO address_relations (
+                address_local_id, relation, local_id, href
+            ) VALUES (?, ?, ?, ?)",
+        )?;
+        for (relation, reference) in relations {
+            statement.execute(rusqlite::params![
+                local_id,
+                relation,
+                reference.local_id,
+                reference.href
+            ])?;
+        }
+        Ok(())
+    }
+
+    /// Inserts the designators and locator names of an address into `address_locators`.
+    fn insert_address_locators<'b>(
+        &self,
+        local_id: &str,
+        rows: impl Iterator<Item = LocatorRow<'b>>,
+    ) -> rusqlite::Result<()> {
+        let mut statement = self.db.prepare_cached(
+            "INSERT INTO address_locators (
+                address_local_id, locator, level, kind, type, value
+            ) VALUES (?, ?, ?, ?, ?, ?)",
+        )?;
+        for row in rows {
+            statement.execute(rusqlite::params![
+                local_id,
+                row.locator,
+                row.level,
+                row.kind,
+                row.value_type,
+                row.value
+            ])?;
+        }
         Ok(())
     }
 }
 
 impl FeatureMemberEmitter for SqliteEmitter {
-    fn emit(&mut self, feature_member: FeatureMember) {
+    fn emit(&mut self, feature_member: FeatureMember<'_>) -> Result<()> {
         match feature_member {
             FeatureMember::Address {
                 local_id,
@@ -78,76 +290,235 @@ impl FeatureMemberEmitter for SqliteEmitter {
                 admin_unit_ref,
                 address_area_ref,
                 thoroughfare_ref,
+                postal_descriptor_ref,
+                other_component_refs,
+                parent_address_ref,
+                building_refs,
+                locators,
+                position,
+                lifecycle,
+                identifier,
+            } => {
+                let coordinates: Vec<Option<f64>> = match &position {
+                    Some(position) => self
+                        .coordinate_output
+                        .values(position.x, position.y)
+                        .into_iter()
+                        .map(Some)
+                        .collect(),
+                    None => vec![None; self.coordinate_output.columns().len()],
+                };
+                let position_method = position
+                    .as_ref()
+                    .and_then(|position| position.method.as_deref());
+                let position_specification = position
+                    .as_ref()
+                    .and_then(|position| position.specification.as_deref());
+                let position_default = position.as_ref().map(|position| position.default);
+
+                let [
+                    admin_unit_ref,
+                    address_area_ref,
+                    thoroughfare_ref,
+                    postal_descriptor_ref,
+                ] = [
+                    admin_unit_ref,
+                    address_area_ref,
+                    thoroughfare_ref,
+                    postal_descriptor_ref,
+                ]
+                .map(|reference| reference.map(|reference| reference.local_id));
+
+                let mut params: Vec<&dyn ToSql> = vec![
+                    &local_id,
+                    &number,
+                    &number_extension,
+                    &number_2nd_extension,
+                    &postal_delivery_identifier,
+                    &admin_unit_ref,
+                    &address_area_ref,
+                    &thoroughfare_ref,
+                    &postal_descriptor_ref,
+                ];
+                params.extend(
+                    coordinates
+                        .iter()
+                        .map(|coordinate| coordinate as &dyn ToSql),
+                );
+                params.extend([
+                    &position_method as &dyn ToSql,
+                    &position_specification,
+                    &position_default,
+                    &lifecycle.status,
+                    &lifecycle.valid_from,
+                    &lifecycle.valid_to,
+                    &lifecycle.begin_lifespan_version,
+                    &lifecycle.end_lifespan_version,
+                    &identifier.version_id,
+                    &identifier.namespace,
+                    &identifier.gml_id,
+                ]);
+                self.db
+                    .execute(&self.insert_address_sql, params.as_slice())?;
+                self.insert_address_relations(
+                    &local_id,
+                    address_relations(parent_address_ref, building_refs, other_component_refs),
+                )?;
+                self.insert_address_locators(&local_id, locator_rows(&locators))?;
+            }
+            FeatureMember::AdminUnitName {
+                local_id,
+                name,
+                names,
+                lifecycle,
+                identifier,
             } => {
                 self.db.execute(
-                    "INSERT INTO addresses (
-                        local_id, number, number_extension, number_2nd_extension,
-                        postal_delivery_identifier, admin_unit_ref, address_area_ref, thoroughfare_ref
-                    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                    "INSERT INTO admin_units (
+                        local_id, name, status, valid_from, valid_to,
+                        begin_lifespan_version, end_lifespan_version, version_id,
+                        namespace, gml_id
+                    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
                     rusqlite::params![
                         local_id,
-                        number,
-                        number_extension,
-                        number_2nd_extension,
-                        postal_delivery_identifier,
-                        admin_unit_ref,
-                        address_area_ref,
-                        thoroughfare_ref
+                        name,
+                        lifecycle.status,
+                        lifecycle.valid_from,
+                        lifecycle.valid_to,
+                        lifecycle.begin_lifespan_version,
+                        lifecycle.end_lifespan_version,
+                        identifier.version_id,
+                        identifier.namespace,
+                        identifier.gml_id
                     ],
-                ).expect("Failed to write to address file");
-            }
-            FeatureMember::AdminUnitName { local_id, name } => {
-                self.db
-                    .execute(
-                        "INSERT INTO admin_units (local_id, name) VALUES (?, ?)",
-                        rusqlite::params![local_id, name],
-                    )
-                    .expect("Failed to write to admin unit file");
+                )?;
+                self.insert_names("admin_units", &local_id, &names)?;
             }
             FeatureMember::AddressAreaName {
                 local_id,
                 name,
+                names,
                 situated_in_ref,
+                lifecycle,
+                identifier,
             } => {
                 self.db.execute(
-                    "INSERT INTO address_areas (local_id, name, situated_in_ref) VALUES (?, ?, ?)",
-                    rusqlite::params![local_id, name, situated_in_ref],
-                ).expect("Failed to write to address area file");
+                    "INSERT INTO address_areas (
+                        local_id, name, situated_in_ref, status, valid_from, valid_to,
+                        begin_lifespan_version, end_lifespan_version, version_id,
+                        namespace, gml_id
+                    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                    rusqlite::params![
+                        local_id,
+                        name,
+                        situated_in_ref.map(|reference| reference.local_id),
+                        lifecycle.status,
+                        lifecycle.valid_from,
+                        lifecycle.valid_to,
+                        lifecycle.begin_lifespan_version,
+                        lifecycle.end_lifespan_version,
+                        identifier.version_id,
+                        identifier.namespace,
+                        identifier.gml_id
+                    ],
+                )?;
+                self.insert_names("address_areas", &local_id, &names)?;
             }
             FeatureMember::ThoroughfareName {
                 local_id,
                 name,
+                names,
                 situated_in_ref,
+                lifecycle,
+                identifier,
             } => {
                 self.db.execute(
-                    "INSERT INTO thoroughfares (local_id, name, situated_in_ref) VALUES (?, ?, ?)",
-                    rusqlite::params![local_id, name, situated_in_ref],
-                ).expect("Failed to write to thoroughfare file");
+                    "INSERT INTO thoroughfares (
+                        local_id, name, situated_in_ref, status, valid_from, valid_to,
+                        begin_lifespan_version, end_lifespan_version, version_id,
+                        namespace, gml_id
+                    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                    rusqlite::params![
+                        local_id,
+                        name,
+                        situated_in_ref.map(|reference| reference.local_id),
+                        lifecycle.status,
+                        lifecycle.valid_from,
+                        lifecycle.valid_to,
+                        lifecycle.begin_lifespan_version,
+                        lifecycle.end_lifespan_version,
+                        identifier.version_id,
+                        identifier.namespace,
+                        identifier.gml_id
+                    ],
+                )?;
+                self.insert_names("thoroughfares", &local_id, &names)?;
+            }
+            FeatureMember::PostalDescriptor {
+                local_id,
+                post_code,
+                post_name,
+                admin_unit_ref,
+                lifecycle,
+                identifier,
+            } => {
+                self.db.execute(
+                    "INSERT INTO postal_descriptors (
+                        local_id, post_code, post_name, admin_unit_ref, status, valid_from,
+                        valid_to, begin_lifespan_version, end_lifespan_version, version_id,
+                        namespace, gml_id
+                    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                    rusqlite::params![
+                        local_id,
+                        post_code,
+                        post_name,
+                        admin_unit_ref.map(|reference| reference.local_id),
+                        lifecycle.status,
+                        lifecycle.valid_from,
+                        lifecycle.valid_to,
+                        lifecycle.begin_lifespan_version,
+                        lifecycle.end_lifespan_version,
+                        identifier.version_id,
+                        identifier.namespace,
+                        identifier.gml_id
+                    ],
+                )?;
             }
         }
+        Ok(())
     }
 
-    fn start(&mut self) {
+    fn start(&mut self) -> Result<()> {
         // Truncate tables
-        self.db
-            .execute("DELETE FROM addresses", [])
-            .expect("Failed to truncate addresses table");
-        self.db
-            .execute("DELETE FROM admin_units", [])
-            .expect("Failed to truncate admin units table");
-        self.db
-            .execute("DELETE FROM address_areas", [])
-            .expect("Failed to truncate address areas table");
-        self.db.execute("DELETE FROM thoroughfares", [])
-            .expect("Failed to truncate thoroughfares table");
-        self.db
-            .execute("BEGIN TRANSACTION", [])
-            .expect("Failed to begin transaction");
+        self.db.execute("DELETE FROM addresses", [])?;
+        self.db.execute("DELETE FROM admin_units", [])?;
+        self.db.execute("DELETE FROM address_areas", [])?;
+        self.db.execute("DELETE FROM thoroughfares", [])?;
+        self.db.execute("DELETE FROM postal_descriptors", [])?;
+        self.db.execute("DELETE FROM address_relations", [])?;
+        self.db.execute("DELETE FROM address_locators", [])?;
+        self.db.execute("DELETE FROM names", [])?;
+        self.db.execute("BEGIN TRANSACTION", [])?;
+        Ok(())
     }
 
-    fn end(&mut self) {
-        self.db
-            .execute("COMMIT", [])
-            .expect("Failed to commit transaction");
+    fn end(&mut self) -> Result<()> {
+        self.db.execute("COMMIT", [])?;
+        Ok(())
     }
 }
+
+fn insert_address_sql(coordinate_output: CoordinateOutput) -> String {
+    let columns = coordinate_output.columns();
+    format!(
+        "INSERT INTO addresses (
+            local_id, number, number_extension, number_2nd_extension,
+            postal_delivery_identifier, admin_unit_ref, address_area_ref, thoroughfare_ref,
+            postal_descriptor_ref, {}, position_method, position_specification, position_default,
+            status, valid_from, valid_to, begin_lifespan_version, end_lifespan_version, version_id,
+            namespace, gml_id
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, {}?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        columns.join(", "),
+        "?, ".repeat(columns.len())
+    )
+}
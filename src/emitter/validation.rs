@@ -0,0 +1,394 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::Write;
+
+use crate::{FeatureMember, Reference, error::Result};
+
+use super::FeatureMemberEmitter;
+
+/// The types of feature, named like the tables of the other emitters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FeatureKind {
+    Address,
+    AdminUnit,
+    AddressArea,
+    Thoroughfare,
+    PostalDescriptor,
+}
+
+impl FeatureKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FeatureKind::Address => "address",
+            FeatureKind::AdminUnit => "admin_unit",
+            FeatureKind::AddressArea => "address_area",
+            FeatureKind::Thoroughfare => "thoroughfare",
+            FeatureKind::PostalDescriptor => "postal_descriptor",
+        }
+    }
+}
+
+/// A feature that refers to another one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Referrer {
+    pub kind: FeatureKind,
+    pub local_id: String,
+    /// The field holding the reference, e.g. `thoroughfare_ref`.
+    pub field: &'static str,
+}
+
+/// A local ID that is referred to, but that no feature of the expected type has.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DanglingReference {
+    pub kind: FeatureKind,
+    pub local_id: String,
+    /// How many references there are to it.
+    pub references: u64,
+    /// The first feature that refers to it.
+    pub first_referrer: Referrer,
+}
+
+/// A local ID that more than one feature of the same type has.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Duplicate {
+    pub kind: FeatureKind,
+    pub local_id: String,
+    pub count: u64,
+}
+
+/// The outcome of validating a document, sorted by type and local ID.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// The number of features per type.
+    pub features: BTreeMap<FeatureKind, u64>,
+    pub dangling_references: Vec<DanglingReference>,
+    /// Names and postal descriptors that no address refers to.
+    pub orphans: Vec<(FeatureKind, String)>,
+    pub duplicates: Vec<Duplicate>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.dangling_references.is_empty() && self.orphans.is_empty() && self.duplicates.is_empty()
+    }
+
+    /// Writes the report as a JSON object.
+    pub fn write_json(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        write!(writer, "{{\"valid\":{},\"features\":{{", self.is_valid())?;
+        for (index, (kind, count)) in self.features.iter().enumerate() {
+            let separator = if index > 0 { "," } else { "" };
+            write!(writer, "{}\"{}\":{}", separator, kind.as_str(), count)?;
+        }
+
+        write!(writer, "}},\"dangling_references\":[")?;
+        for (index, dangling) in self.dangling_references.iter().enumerate() {
+            let referrer = &dangling.first_referrer;
+            write!(
+                writer,
+                "{}{{\"type\":\"{}\",\"local_id\":{},\"references\":{},\"first_referrer\":{{\"type\":\"{}\",\"local_id\":{},\"field\":\"{}\"}}}}",
+                if index > 0 { "," } else { "" },
+                dangling.kind.as_str(),
+                json_string(&dangling.local_id),
+                dangling.references,
+                referrer.kind.as_str(),
+                json_string(&referrer.local_id),
+                referrer.field
+            )?;
+        }
+
+        write!(writer, "],\"orphans\":[")?;
+        for (index, (kind, local_id)) in self.orphans.iter().enumerate() {
+            write!(
+                writer,
+                "{}{{\"type\":\"{}\",\"local_id\":{}}}",
+                if index > 0 { "," } else { "" },
+                kind.as_str(),
+                json_string(local_id)
+            )?;
+        }
+
+        write!(writer, "],\"duplicates\":[")?;
+        for (index, duplicate) in self.duplicates.iter().enumerate() {
+            write!(
+                writer,
+                "{}{{\"type\":\"{}\",\"local_id\":{},\"count\":{}}}",
+                if index > 0 { "," } else { "" },
+                duplicate.kind.as_str(),
+                json_string(&duplicate.local_id),
+                duplicate.count
+            )?;
+        }
+        writeln!(writer, "]}}")
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c < ' ' => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn hash(local_id: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    local_id.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// What is known about a referenced local ID.
+struct Target {
+    references: u64,
+    referenced_by_address: bool,
+    first_referrer: Referrer,
+}
+
+/// Checks the references between features instead of writing them anywhere.
+///
+/// References can point forward, so they are only checked in [`end`](FeatureMemberEmitter::end),
+/// after which the report is available from [`ValidationEmitter::report`]. The references of a
+/// feature are checked against the type the field implies: `situated_in_ref` of an address area
+/// and `admin_unit_ref` of a postal descriptor against admin units, and `situated_in_ref` of a
+/// thoroughfare against address areas. Building references are not checked, as buildings are not
+/// part of the dataset.
+///
+/// Only a 64-bit hash of the local ID of every address is kept, to find duplicates without
+/// keeping millions of IDs in memory.
+#[derive(Default)]
+pub struct ValidationEmitter {
+    features: BTreeMap<FeatureKind, u64>,
+    address_ids: HashSet<u64>,
+    /// The local IDs of every type of feature other than addresses.
+    name_ids: HashMap<FeatureKind, HashSet<String>>,
+    duplicates: HashMap<(FeatureKind, String), u64>,
+    targets: HashMap<(FeatureKind, String), Target>,
+    report: Option<ValidationReport>,
+}
+
+impl ValidationEmitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the report, once the emitter has ended.
+    pub fn report(&self) -> Option<&ValidationReport> {
+        self.report.as_ref()
+    }
+
+    fn is_defined(&self, kind: FeatureKind, local_id: &str) -> bool {
+        if kind == FeatureKind::Address {
+            self.address_ids.contains(&hash(local_id))
+        } else {
+            self.name_ids
+                .get(&kind)
+                .is_some_and(|local_ids| local_ids.contains(local_id))
+        }
+    }
+
+    fn define(&mut self, kind: FeatureKind, local_id: &str) {
+        *self.features.entry(kind).or_default() += 1;
+        let is_new = if kind == FeatureKind::Address {
+            self.address_ids.insert(hash(local_id))
+        } else {
+            self.name_ids
+                .entry(kind)
+                .or_default()
+                .insert(local_id.to_string())
+        };
+        if !is_new {
+            // Counts every occurrence, including the first
+            *self
+                .duplicates
+                .entry((kind, local_id.to_string()))
+                .or_insert(1) += 1;
+        }
+    }
+
+    fn refer(
+        &mut self,
+        referrer_kind: FeatureKind,
+        referrer_local_id: &str,
+        field: &'static str,
+        kind: FeatureKind,
+        reference: Option<Reference>,
+    ) {
+        let Some(reference) = reference else {
+            return;
+        };
+        let target = self
+            .targets
+            .entry((kind, reference.local_id))
+            .or_insert_with(|| Target {
+                references: 0,
+                referenced_by_address: false,
+                first_referrer: Referrer {
+                    kind: referrer_kind,
+                    local_id: referrer_local_id.to_string(),
+                    field,
+                },
+            });
+        target.references += 1;
+        target.referenced_by_address |= referrer_kind == FeatureKind::Address;
+    }
+}
+
+impl FeatureMemberEmitter for ValidationEmitter {
+    fn emit(&mut self, feature_member: FeatureMember<'_>) -> Result<()> {
+        match feature_member {
+            FeatureMember::Address {
+                local_id,
+                admin_unit_ref,
+                address_area_ref,
+                thoroughfare_ref,
+                postal_descriptor_ref,
+                parent_address_ref,
+                ..
+            } => {
+                let kind = FeatureKind::Address;
+                self.define(kind, &local_id);
+                self.refer(
+                    kind,
+                    &local_id,
+                    "admin_unit_ref",
+                    FeatureKind::AdminUnit,
+                    admin_unit_ref,
+                );
+                self.refer(
+                    kind,
+                    &local_id,
+                    "address_area_ref",
+                    FeatureKind::AddressArea,
+                    address_area_ref,
+                );
+                self.refer(
+                    kind,
+                    &local_id,
+                    "thoroughfare_ref",
+                    FeatureKind::Thoroughfare,
+                    thoroughfare_ref,
+                );
+                self.refer(
+                    kind,
+                    &local_id,
+                    "postal_descriptor_ref",
+                    FeatureKind::PostalDescriptor,
+                    postal_descriptor_ref,
+                );
+                self.refer(
+                    kind,
+                    &local_id,
+                    "parent_address_ref",
+                    FeatureKind::Address,
+                    parent_address_ref,
+                );
+            }
+            FeatureMember::AdminUnitName { local_id, .. } => {
+                self.define(FeatureKind::AdminUnit, &local_id);
+            }
+            FeatureMember::AddressAreaName {
+                local_id,
+                situated_in_ref,
+                ..
+            } => {
+                let kind = FeatureKind::AddressArea;
+                self.define(kind, &local_id);
+                self.refer(
+                    kind,
+                    &local_id,
+                    "situated_in_ref",
+                    FeatureKind::AdminUnit,
+                    situated_in_ref,
+                );
+            }
+            FeatureMember::ThoroughfareName {
+                local_id,
+                situated_in_ref,
+                ..
+            } => {
+                let kind = FeatureKind::Thoroughfare;
+                self.define(kind, &local_id);
+                self.refer(
+                    kind,
+                    &local_id,
+                    "situated_in_ref",
+                    FeatureKind::AddressArea,
+                    situated_in_ref,
+                );
+            }
+            FeatureMember::PostalDescriptor {
+                local_id,
+                admin_unit_ref,
+                ..
+            } => {
+                let kind = FeatureKind::PostalDescriptor;
+                self.define(kind, &local_id);
+                self.refer(
+                    kind,
+                    &local_id,
+                    "admin_unit_ref",
+                    FeatureKind::AdminUnit,
+                    admin_unit_ref,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn end(&mut self) -> Result<()> {
+        let mut dangling_references: Vec<_> = self
+            .targets
+            .iter()
+            .filter(|((kind, local_id), _)| !self.is_defined(*kind, local_id))
+            .map(|((kind, local_id), target)| DanglingReference {
+                kind: *kind,
+                local_id: local_id.clone(),
+                references: target.references,
+                first_referrer: target.first_referrer.clone(),
+            })
+            .collect();
+        dangling_references.sort_by(|a, b| (a.kind, &a.local_id).cmp(&(b.kind, &b.local_id)));
+
+        let mut orphans: Vec<_> = self
+            .name_ids
+            .iter()
+            .flat_map(|(kind, local_ids)| local_ids.iter().map(|local_id| (*kind, local_id)))
+            .filter(|(kind, local_id)| {
+                !self
+                    .targets
+                    .get(&(*kind, local_id.to_string()))
+                    .is_some_and(|target| target.referenced_by_address)
+            })
+            .map(|(kind, local_id)| (kind, local_id.clone()))
+            .collect();
+        orphans.sort();
+
+        let mut duplicates: Vec<_> = self
+            .duplicates
+            .iter()
+            .map(|((kind, local_id), count)| Duplicate {
+                kind: *kind,
+                local_id: local_id.clone(),
+                count: *count,
+            })
+            .collect();
+        duplicates.sort_by(|a, b| (a.kind, &a.local_id).cmp(&(b.kind, &b.local_id)));
+
+        self.report = Some(ValidationReport {
+            features: self.features.clone(),
+            dangling_references,
+            orphans,
+            duplicates,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,174 @@
+use std::fmt;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Reading the input or writing the output failed.
+    Io(std::io::Error),
+    /// The input is not well-formed XML.
+    Xml {
+        position: u64,
+        /// The line, counting from 1, of the markup the error is in.
+        line: u64,
+        source: quick_xml::Error,
+    },
+    /// The input is well-formed XML, but not a document the parser understands.
+    Parse {
+        position: u64,
+        /// The line, counting from 1, of the markup the error is in.
+        line: u64,
+        path: String,
+        kind: ParseErrorKind,
+    },
+    Sqlite(rusqlite::Error),
+    /// The input is a zip archive that can't be read.
+    Zip(zip::result::ZipError),
+    /// Downloading failed.
+    Http(Box<ureq::Error>),
+    /// A web service answered with an exception report instead of features.
+    Service {
+        url: String,
+        message: String,
+    },
+    /// The document declares an encoding that is not known.
+    UnknownEncoding(String),
+    /// A download does not have the size or checksum it should have.
+    Verification {
+        url: String,
+        expected: String,
+        found: String,
+    },
+    /// A thread of the parallel parser panicked.
+    ThreadPanicked,
+}
+
+#[derive(Debug)]
+pub enum ParseErrorKind {
+    UnknownFeatureMember(String),
+    MismatchedEndTag {
+        expected: Option<String>,
+        found: String,
+    },
+    UnexpectedElement(String),
+    MissingLocalId,
+    InvalidUtf8(std::str::Utf8Error),
+    InvalidPosition(String),
+    InvalidDateTime(String),
+    UnknownPrefix(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(error) => write!(f, "I/O error: {}", error),
+            Error::Xml {
+                position,
+                line,
+                source,
+            } => write!(
+                f,
+                "XML error at line {} (byte {}): {}",
+                line, position, source
+            ),
+            Error::Parse {
+                position,
+                line,
+                path,
+                kind,
+            } => write!(
+                f,
+                "parse error at line {} (byte {}) ({}): {}",
+                line, position, path, kind
+            ),
+            Error::Sqlite(error) => write!(f, "SQLite error: {}", error),
+            Error::Zip(error) => write!(f, "zip error: {}", error),
+            Error::Http(error) => write!(f, "HTTP error: {}", error),
+            Error::Service { url, message } => write!(f, "{} reported: {}", url, message),
+            Error::UnknownEncoding(label) => write!(f, "unknown encoding {:?}", label),
+            Error::Verification {
+                url,
+                expected,
+                found,
+            } => write!(
+                f,
+                "download {} is corrupt: expected {}, found {}",
+                url, expected, found
+            ),
+            Error::ThreadPanicked => write!(f, "a parser thread stopped early"),
+        }
+    }
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::UnknownFeatureMember(tag) => {
+                write!(f, "unknown feature member tag {}", tag)
+            }
+            ParseErrorKind::MismatchedEndTag {
+                expected: Some(expected),
+                found,
+            } => write!(f, "expected end tag {}, found {}", expected, found),
+            ParseErrorKind::MismatchedEndTag {
+                expected: None,
+                found,
+            } => write!(f, "unexpected end tag {}", found),
+            ParseErrorKind::UnexpectedElement(tag) => {
+                write!(f, "element {} is not part of a feature member", tag)
+            }
+            ParseErrorKind::MissingLocalId => write!(f, "feature member has no local ID"),
+            ParseErrorKind::InvalidUtf8(error) => write!(f, "invalid UTF-8: {}", error),
+            ParseErrorKind::InvalidPosition(pos) => write!(f, "invalid position {:?}", pos),
+            ParseErrorKind::InvalidDateTime(text) => write!(f, "invalid date/time {:?}", text),
+            ParseErrorKind::UnknownPrefix(prefix) => {
+                write!(f, "undeclared namespace prefix {}", prefix)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            Error::Xml { source, .. } => Some(source),
+            Error::Parse {
+                kind: ParseErrorKind::InvalidUtf8(error),
+                ..
+            } => Some(error),
+            Error::Parse { .. } => None,
+            Error::Sqlite(error) => Some(error),
+            Error::Zip(error) => Some(error),
+            Error::Http(error) => Some(error),
+            Error::Service { .. }
+            | Error::UnknownEncoding(_)
+            | Error::Verification { .. }
+            | Error::ThreadPanicked => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(error: rusqlite::Error) -> Self {
+        Error::Sqlite(error)
+    }
+}
+
+impl From<zip::result::ZipError> for Error {
+    fn from(error: zip::result::ZipError) -> Self {
+        Error::Zip(error)
+    }
+}
+
+impl From<ureq::Error> for Error {
+    fn from(error: ureq::Error) -> Self {
+        Error::Http(Box::new(error))
+    }
+}
@@ -1,33 +1,178 @@
+pub mod atom;
+pub mod crs;
 pub mod emitter;
+pub mod error;
+pub mod parser;
+pub mod resolve;
+pub mod source;
+pub mod wfs;
 
-pub enum FeatureMember {
+pub use error::{Error, Result};
+pub use parser::{
+    BorrowedFeatureMemberIter, FeatureMemberIter, Mode, ParallelParser, Parser, Selection,
+};
+
+use std::borrow::Cow;
+
+use chrono::NaiveDateTime;
+
+/// A feature read from the document.
+///
+/// Text is borrowed from the document when it is parsed from a slice, such as a memory-mapped
+/// file (see [`Parser::parse_borrowed`]), and owned otherwise.
+// Addresses are by far the most common feature, so boxing them would only add an allocation
+#[allow(clippy::large_enum_variant)]
+pub enum FeatureMember<'a> {
     Address {
-        local_id: String,
-        number: Option<String>,
-        number_extension: Option<String>,
-        number_2nd_extension: Option<String>,
-        postal_delivery_identifier: Option<String>,
-        admin_unit_ref: Option<String>,
-        address_area_ref: Option<String>,
-        thoroughfare_ref: Option<String>,
+        local_id: Cow<'a, str>,
+        /// The first designator of each type in [`locators`](FeatureMember::Address::locators).
+        number: Option<Cow<'a, str>>,
+        number_extension: Option<Cow<'a, str>>,
+        number_2nd_extension: Option<Cow<'a, str>>,
+        postal_delivery_identifier: Option<Cow<'a, str>>,
+        admin_unit_ref: Option<Reference>,
+        address_area_ref: Option<Reference>,
+        thoroughfare_ref: Option<Reference>,
+        postal_descriptor_ref: Option<Reference>,
+        /// Components whose type can't be determined from the document or their identifier,
+        /// such as `#gml-id` references to features that only appear later.
+        other_component_refs: Vec<Reference>,
+        /// The address this one is part of, such as the address of the building an apartment is
+        /// in.
+        parent_address_ref: Option<Reference>,
+        /// The buildings the address belongs to. These are not part of the dataset, so their
+        /// local IDs are taken from the references.
+        building_refs: Vec<Reference>,
+        locators: Vec<Locator<'a>>,
+        position: Option<Position>,
+        lifecycle: Lifecycle,
+        identifier: Identifier<'a>,
     },
     // Country
     AdminUnitName {
-        local_id: String,
-        name: Option<String>,
+        local_id: Cow<'a, str>,
+        /// The first spelling of the first name.
+        name: Option<Cow<'a, str>>,
+        names: Vec<GeographicalName<'a>>,
+        lifecycle: Lifecycle,
+        identifier: Identifier<'a>,
     },
     // City
     AddressAreaName {
-        local_id: String,
-        name: Option<String>,
-        situated_in_ref: Option<String>,
+        local_id: Cow<'a, str>,
+        /// The first spelling of the first name.
+        name: Option<Cow<'a, str>>,
+        names: Vec<GeographicalName<'a>>,
+        situated_in_ref: Option<Reference>,
+        lifecycle: Lifecycle,
+        identifier: Identifier<'a>,
     },
     // Street
     ThoroughfareName {
-        local_id: String,
-        name: Option<String>,
-        situated_in_ref: Option<String>,
+        local_id: Cow<'a, str>,
+        /// The first spelling of the first name.
+        name: Option<Cow<'a, str>>,
+        names: Vec<GeographicalName<'a>>,
+        situated_in_ref: Option<Reference>,
+        lifecycle: Lifecycle,
+        identifier: Identifier<'a>,
     },
+    // Postcode
+    PostalDescriptor {
+        local_id: Cow<'a, str>,
+        post_code: Option<Cow<'a, str>>,
+        post_name: Option<Cow<'a, str>>,
+        admin_unit_ref: Option<Reference>,
+        lifecycle: Lifecycle,
+        identifier: Identifier<'a>,
+    },
+}
+
+/// Life-cycle information that every INSPIRE address feature carries.
+#[derive(Default)]
+pub struct Lifecycle {
+    /// The `StatusValue` code, e.g. `current`, `retired` or `proposed`.
+    pub status: Option<String>,
+    pub valid_from: Option<NaiveDateTime>,
+    pub valid_to: Option<NaiveDateTime>,
+    pub begin_lifespan_version: Option<NaiveDateTime>,
+    pub end_lifespan_version: Option<NaiveDateTime>,
+}
+
+/// The identifiers of a feature besides its local ID.
+#[derive(Default)]
+pub struct Identifier<'a> {
+    /// The `base:versionId` of the feature's INSPIRE identifier.
+    pub version_id: Option<Cow<'a, str>>,
+    /// The `base:namespace` of the feature's INSPIRE identifier, e.g. `NL.IMBAG.Nummeraanduiding`.
+    pub namespace: Option<Cow<'a, str>>,
+    /// The `gml:id` of the feature, which only identifies it within the document.
+    pub gml_id: Option<String>,
+}
+
+/// A name of a feature (`gn:GeographicalName`), e.g. the Dutch or the Frisian name of a
+/// municipality.
+#[derive(Default)]
+pub struct GeographicalName<'a> {
+    /// The ISO 639-3 language code, e.g. `dut` or `fry`.
+    pub language: Option<Cow<'a, str>>,
+    /// The `NameStatusValue` code, e.g. `official` or `standardised`.
+    pub name_status: Option<String>,
+    pub spellings: Vec<Spelling<'a>>,
+}
+
+/// One way of writing a [`GeographicalName`] (`gn:SpellingOfName`).
+pub struct Spelling<'a> {
+    pub text: Cow<'a, str>,
+    /// The ISO 15924 script code, e.g. `Latn`.
+    pub script: Option<Cow<'a, str>>,
+}
+
+/// What identifies an address within the thoroughfare or area it is in (`ad:AddressLocator`), e.g.
+/// a house number, or the number of an apartment within a building.
+#[derive(Default)]
+pub struct Locator<'a> {
+    /// The `LocatorLevelValue` code, e.g. `unitLevel`.
+    pub level: Option<String>,
+    pub designators: Vec<Designator<'a>>,
+    pub names: Vec<LocatorName<'a>>,
+}
+
+/// A number or code that identifies an address (`ad:LocatorDesignator`).
+#[derive(Default)]
+pub struct Designator<'a> {
+    /// The `LocatorDesignatorTypeValue` code, e.g. `addressNumber`.
+    pub designator_type: Option<String>,
+    /// The designator, which is nil for some BAG addresses.
+    pub designator: Option<Cow<'a, str>>,
+}
+
+/// A name that identifies an address (`ad:LocatorName`), e.g. the name of a building.
+#[derive(Default)]
+pub struct LocatorName<'a> {
+    /// The `LocatorNameTypeValue` code, e.g. `buildingName`.
+    pub name_type: Option<String>,
+    pub names: Vec<GeographicalName<'a>>,
+}
+
+/// A reference from one feature to another.
+pub struct Reference {
+    /// The `xlink:href` as found in the document, e.g. `#nl-imbag-ad-adminunitname.0003`.
+    pub href: String,
+    /// The local ID of the referenced feature, e.g. `0003`.
+    pub local_id: String,
+}
+
+/// A geographic position of an address, in the coordinate reference system of the dataset
+/// (EPSG:28992 for the Dutch BAG).
+pub struct Position {
+    pub x: f64,
+    pub y: f64,
+    /// The `GeometryMethodValue` code, e.g. `byOtherParty`.
+    pub method: Option<String>,
+    /// The `GeometrySpecificationValue` code, e.g. `entrance`.
+    pub specification: Option<String>,
+    pub default: bool,
 }
 
 type IntRef = u16;
@@ -1,725 +1,426 @@
-use core::{panic, str};
-use std::{path::PathBuf, sync::OnceLock};
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
 
 use gmlparser::{
-    FeatureMember, StrRef, StringInterner,
+    Mode, ParallelParser, Parser, Selection, atom,
+    crs::CoordinateOutput,
     emitter::{
         ChooseEmitter, FeatureMemberEmitter, multifile::MultiFileEmitter, null::NullEmitter,
-        sqlite::SqliteEmitter,
+        resolved::ResolvedTsvEmitter, sqlite::SqliteEmitter, validation::ValidationEmitter,
     },
+    resolve::ResolvingEmitter,
+    source,
+    wfs::{BoundingBox, GetFeature},
 };
-use quick_xml::events::Event;
 
-fn main() {
-    // Open the file supplied as the first argument
-    let file_path = std::env::args()
-        .nth(1)
-        .unwrap_or_else(|| "/Users/jelle/Downloads/addresses.gml".to_string());
-    let format = std::env::args().nth(2).unwrap_or_else(|| "tsv".to_string());
-    let file = std::fs::File::open(&file_path).expect("Failed to open file");
-    // Create a buffered reader
-    let reader = std::io::BufReader::new(file);
-    let mut xml_reader = quick_xml::reader::Reader::from_reader(reader);
-
-    let mut buf = Vec::new();
-
-    let emitter: ChooseEmitter = if format == "tsv" {
-        // Create a TSV file emitter
-        let tsv_file_path = PathBuf::from(file_path);
-        MultiFileEmitter::new(tsv_file_path.parent().unwrap()).into()
-    } else if format == "sqlite" {
-        // Create a SQLite file emitter
-        let sqlite_file_path = PathBuf::from(file_path);
-        let sqlite_emitter = SqliteEmitter::new(
-            sqlite_file_path
-                .parent()
-                .expect("unable to get parent path")
-                .join("nl-addresses.sqlite")
-                .as_path(),
-        )
-        .expect("unable to open sqlite file");
-        sqlite_emitter
-            .create_tables()
-            .expect("unable to create tables");
-        sqlite_emitter.into()
-    } else if format == "null" {
-        // Create a null emitter
-        NullEmitter.into()
-    } else {
-        eprintln!("Unknown format: {}", format);
-        return;
-    };
+enum Input {
+    File(String),
+    /// A download from an INSPIRE ATOM feed, or the list of downloads if no entry is given.
+    Atom {
+        feed: String,
+        entry: Option<usize>,
+        cache_dir: PathBuf,
+        sha256: Option<String>,
+    },
+    /// Paged `GetFeature` requests to a WFS.
+    Wfs(GetFeature),
+}
 
-    let mut collector = AddressCollector::new(emitter);
+struct Args {
+    input: Input,
+    format: String,
+    coordinate_output: CoordinateOutput,
+    mode: Mode,
+    selection: Selection,
+    rejects_path: Option<String>,
+    /// Parse on this many threads instead of on the main thread.
+    threads: Option<NonZeroUsize>,
+    /// Parse an uncompressed file from memory instead of reading it.
+    mmap: bool,
+    /// Decode the input from this encoding instead of the one it declares.
+    encoding: Option<&'static encoding_rs::Encoding>,
+    /// Exit with status 0 from `validate` even if the report finds problems.
+    report_only: bool,
+}
 
-    loop {
-        match xml_reader
-            .read_event_into(&mut buf)
-            .expect("Failed to read event")
-        {
-            Event::Start(e) => {
-                collector.visit_start(e);
+fn parse_args() -> Result<Args, String> {
+    let mut positional = Vec::new();
+    let mut coordinate_output = CoordinateOutput::default();
+    let mut mode = Mode::default();
+    let mut selection = Selection::all();
+    let mut rejects_path = None;
+    let mut atom_feed = None;
+    let mut atom_entry = None;
+    let mut cache_dir = PathBuf::from("cache");
+    let mut sha256 = None;
+    let mut threads = None;
+    let mut mmap = false;
+    let mut encoding = None;
+    let mut wfs_url = None;
+    let mut type_names = vec!["ad:Address".to_string()];
+    let mut count = None;
+    let mut bbox = None;
+    let mut filter = None;
+    let mut report_only = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--crs" => {
+                let value = args.next().ok_or("--crs needs one of rd, wgs84 or both")?;
+                coordinate_output = value.parse()?;
+            }
+            "--lenient" => mode = Mode::Lenient,
+            "--only" => {
+                let value = args.next().ok_or("--only needs a list of feature types")?;
+                let feature_types = value
+                    .split(',')
+                    .map(str::parse)
+                    .collect::<Result<Vec<_>, _>>()?;
+                selection = selection.with_feature_types(feature_types);
+            }
+            "--skip" => {
+                let value = args.next().ok_or("--skip needs a list of fields")?;
+                for field in value.split(',') {
+                    selection = selection.without(field.parse()?);
+                }
+            }
+            "--rejects" => {
+                rejects_path = Some(args.next().ok_or("--rejects needs a file path")?);
+                mode = Mode::Lenient;
             }
-            Event::Text(e) => {
-                collector.visit_text(e);
+            "--atom" => atom_feed = Some(args.next().ok_or("--atom needs a URL or file path")?),
+            "--entry" => {
+                let value = args.next().ok_or("--entry needs an entry number")?;
+                atom_entry = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("Invalid entry: {}", value))?,
+                );
             }
-            Event::Empty(e) => {
-                collector.visit_empty(e);
+            "--cache" => cache_dir = args.next().ok_or("--cache needs a directory")?.into(),
+            "--sha256" => sha256 = Some(args.next().ok_or("--sha256 needs a checksum")?),
+            "--threads" => {
+                let value = args.next().ok_or("--threads needs a number of threads")?;
+                threads = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("Invalid number of threads: {}", value))?,
+                );
             }
-            Event::End(e) => {
-                collector.visit_end(e);
+            "--mmap" => mmap = true,
+            "--encoding" => {
+                let value = args.next().ok_or("--encoding needs an encoding name")?;
+                encoding = Some(
+                    encoding_rs::Encoding::for_label(value.as_bytes())
+                        .ok_or_else(|| format!("Unknown encoding: {}", value))?,
+                );
             }
-            Event::Eof => break,
-            _ => {}
+            "--wfs" => wfs_url = Some(args.next().ok_or("--wfs needs a URL")?),
+            "--type-names" => {
+                let value = args.next().ok_or("--type-names needs a list of types")?;
+                type_names = value.split(',').map(str::to_string).collect();
+            }
+            "--count" => {
+                let value = args.next().ok_or("--count needs a number of features")?;
+                count = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("Invalid count: {}", value))?,
+                );
+            }
+            "--bbox" => {
+                let value = args.next().ok_or("--bbox needs min_x,min_y,max_x,max_y")?;
+                bbox = Some(value.parse::<BoundingBox>()?);
+            }
+            "--filter" => filter = Some(args.next().ok_or("--filter needs an fes:Filter")?),
+            "--report-only" => report_only = true,
+            _ => positional.push(arg),
         }
     }
-    collector.end();
-}
-
-type XmlPath = Vec<StrRef>;
 
-fn path_starts_with(path: &XmlPath, prefix: &XmlPath) -> bool {
-    if path.len() < prefix.len() {
-        return false;
+    if mmap && threads.is_some() {
+        return Err("--mmap can't be combined with --threads".into());
     }
-    prefix.iter().zip(path.iter()).all(|(a, b)| a == b)
-}
-
-fn path_ends_with(path: &XmlPath, suffix: &XmlPath) -> bool {
-    if path.len() < suffix.len() {
-        return false;
+    if wfs_url.is_some() && (mmap || threads.is_some() || rejects_path.is_some()) {
+        return Err("--wfs can't be combined with --mmap, --threads or --rejects".into());
+    }
+    if bbox.is_some() && filter.is_some() {
+        return Err("--bbox can't be combined with --filter".into());
     }
-    suffix
-        .iter()
-        .rev()
-        .zip(path.iter().rev())
-        .all(|(a, b)| a == b)
-}
 
-enum FeatureMemberBuilder {
-    Address(AddressBuilder),
-    AdminUnitName(AdminUnitNameBuilder),
-    AddressAreaName(AddressAreaNameBuilder),
-    ThoroughfareName(ThoroughfareNameBuilder),
+    let mut positional = positional.into_iter();
+    let input = match (atom_feed, wfs_url) {
+        (Some(_), Some(_)) => return Err("--atom can't be combined with --wfs".into()),
+        (None, Some(url)) => {
+            let mut get_feature = GetFeature::new(url, type_names)
+                .with_mode(mode)
+                .with_selection(selection.clone());
+            if let Some(count) = count {
+                get_feature = get_feature.with_count(count);
+            }
+            if let Some(bbox) = bbox {
+                get_feature = get_feature.with_bbox(bbox);
+            }
+            if let Some(filter) = filter {
+                get_feature = get_feature.with_filter(filter);
+            }
+            Input::Wfs(get_feature)
+        }
+        (Some(feed), None) => Input::Atom {
+            feed,
+            entry: atom_entry,
+            cache_dir,
+            sha256,
+        },
+        (None, None) => Input::File(
+            positional
+                .next()
+                .unwrap_or_else(|| "/Users/jelle/Downloads/addresses.gml".to_string()),
+        ),
+    };
+    Ok(Args {
+        input,
+        format: positional.next().unwrap_or_else(|| "tsv".to_string()),
+        coordinate_output,
+        mode,
+        selection,
+        rejects_path,
+        threads,
+        mmap,
+        encoding,
+        report_only,
+    })
 }
 
-struct AddressBuilder {
-    number: Option<String>,
-    number_extension: Option<String>,
-    number_2nd_extension: Option<String>,
-    postal_delivery_identifier: Option<String>,
-    admin_unit_ref: Option<String>,
-    address_area_ref: Option<String>,
-    thoroughfare_ref: Option<String>,
-    locator_designator_builder: Option<LocatorDesignatorBuilder>,
-}
-impl AddressBuilder {
-    fn set_designator(&mut self, designator: LocatorDesignator) {
-        match designator.type_ {
-            LocatorDesignatorType::Number => self.number = designator.designator,
-            LocatorDesignatorType::NumberExtension => {
-                self.number_extension = designator.designator
+/// Lists the entries of an ATOM feed, or downloads the first file of the given entry and returns
+/// its path.
+fn atom_input(
+    feed: &str,
+    entry: Option<usize>,
+    cache_dir: &Path,
+    sha256: Option<&str>,
+) -> Result<Option<PathBuf>, String> {
+    let feed = atom::read_feed(feed).map_err(|error| error.to_string())?;
+    let Some(entry) = entry else {
+        for (index, entry) in feed.entries.iter().enumerate() {
+            println!(
+                "{}: {}",
+                index,
+                entry.title.as_deref().unwrap_or("(untitled)")
+            );
+            if let Some(updated) = entry.updated {
+                println!("   updated: {}", updated);
+            }
+            for crs in &entry.crs {
+                println!(
+                    "   crs: {} {}",
+                    crs.uri,
+                    crs.label.as_deref().unwrap_or_default()
+                );
             }
-            LocatorDesignatorType::Number2ndExtension => {
-                self.number_2nd_extension = designator.designator
+            for link in entry.downloads() {
+                println!(
+                    "   download: {} ({}, {} bytes)",
+                    link.href,
+                    link.media_type.as_deref().unwrap_or("unknown format"),
+                    link.length
+                        .map_or_else(|| "unknown".to_string(), |length| length.to_string())
+                );
             }
-            LocatorDesignatorType::PostalDeliveryIdentifier => {
-                self.postal_delivery_identifier = designator.designator
+            if let Some(link) = entry.dataset_feed() {
+                println!("   dataset feed: {}", link.href);
             }
         }
-    }
-}
+        return Ok(None);
+    };
 
-struct LocatorDesignatorBuilder {
-    type_: Option<LocatorDesignatorType>,
-    designator: Option<Option<String>>,
+    let entry = feed
+        .entries
+        .get(entry)
+        .ok_or_else(|| format!("The feed has no entry {}", entry))?;
+    let link = entry
+        .downloads()
+        .next()
+        .ok_or("The entry has no downloads; use --atom with its dataset feed instead")?;
+    eprintln!("Fetching {}", link.href);
+    let path = atom::fetch(link, cache_dir, sha256).map_err(|error| error.to_string())?;
+    Ok(Some(path))
 }
 
-struct LocatorDesignator {
-    type_: LocatorDesignatorType,
-    designator: Option<String>,
+/// Creates the emitter for `format`, or returns `None` if the format is not known.
+fn create_emitter(
+    format: &str,
+    output_dir: &Path,
+    coordinate_output: CoordinateOutput,
+) -> gmlparser::Result<Option<ChooseEmitter>> {
+    let emitter: ChooseEmitter = if format == "tsv" {
+        // Create a TSV file emitter
+        MultiFileEmitter::new(output_dir)?
+            .with_coordinate_output(coordinate_output)
+            .into()
+    } else if format == "sqlite" {
+        // Create a SQLite file emitter
+        let sqlite_emitter = SqliteEmitter::new(output_dir.join("nl-addresses.sqlite").as_path())?
+            .with_coordinate_output(coordinate_output);
+        sqlite_emitter.create_tables()?;
+        sqlite_emitter.into()
+    } else if format == "resolved" {
+        // Create a TSV file emitter for addresses joined with their names
+        ResolvingEmitter::new(ResolvedTsvEmitter::new(output_dir)?).into()
+    } else if format == "validate" {
+        // Check the references between features instead of writing them
+        ValidationEmitter::new().into()
+    } else if format == "null" {
+        // Create a null emitter
+        NullEmitter.into()
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(emitter))
 }
 
-impl LocatorDesignatorBuilder {
-    fn new_with_type(type_: LocatorDesignatorType) -> LocatorDesignatorBuilder {
-        LocatorDesignatorBuilder {
-            type_: Some(type_),
-            designator: None,
+fn main() {
+    let Args {
+        input,
+        format,
+        coordinate_output,
+        mode,
+        selection,
+        rejects_path,
+        threads,
+        mmap,
+        encoding,
+        report_only,
+    } = match parse_args() {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(2);
         }
-    }
+    };
 
-    fn new_with_designator(designator: String) -> LocatorDesignatorBuilder {
-        LocatorDesignatorBuilder {
-            type_: None,
-            designator: Some(Some(designator)),
-        }
-    }
+    let (file_path, mut get_feature) = match input {
+        Input::File(file_path) => (file_path, None),
+        Input::Atom {
+            feed,
+            entry,
+            cache_dir,
+            sha256,
+        } => match atom_input(&feed, entry, &cache_dir, sha256.as_deref()) {
+            Ok(Some(path)) => (path.to_string_lossy().into_owned(), None),
+            Ok(None) => return,
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        },
+        Input::Wfs(get_feature) => (String::new(), Some(get_feature)),
+    };
+    // Output goes next to the input file, or into the current directory for a WFS
+    let output_dir = Path::new(&file_path).parent().unwrap_or(Path::new(""));
 
-    fn new_with_none_designator() -> LocatorDesignatorBuilder {
-        LocatorDesignatorBuilder {
-            type_: None,
-            designator: Some(None),
+    let mut emitter = match create_emitter(&format, output_dir, coordinate_output) {
+        Ok(Some(emitter)) => emitter,
+        Ok(None) => {
+            eprintln!("Unknown format: {}", format);
+            return;
         }
-    }
-
-    fn set_type(&mut self, type_: LocatorDesignatorType) -> Option<LocatorDesignator> {
-        if let Some(designator) = self.designator.take() {
-            return Some(LocatorDesignator { type_, designator });
+        Err(error) => {
+            eprintln!("Unable to create the {} output: {}", format, error);
+            std::process::exit(1);
         }
-        self.type_ = Some(type_);
-        None
-    }
+    };
 
-    fn set_designator(&mut self, designator: String) -> Option<LocatorDesignator> {
-        if let Some(type_) = self.type_.take() {
-            return Some(LocatorDesignator { type_, designator: Some(designator) });
+    let rejects = match rejects_path.map(std::fs::File::create).transpose() {
+        Ok(rejects) => rejects,
+        Err(error) => {
+            eprintln!("Unable to create the rejects file: {}", error);
+            std::process::exit(1);
         }
-        self.designator = Some(Some(designator));
-        None
-
-    }
+    };
 
-    fn set_designator_none(&mut self) -> Option<LocatorDesignator> {
-        if let Some(type_) = self.type_.take() {
-            return Some(LocatorDesignator {
-                type_,
-                designator: None,
-            });
+    let mut skipped = BTreeMap::<String, u64>::new();
+    let result = (|| {
+        emitter.start()?;
+        if let Some(get_feature) = &mut get_feature {
+            get_feature.for_each(|feature_member| emitter.emit(feature_member))?;
+            skipped.clone_from(get_feature.skipped());
+            return emitter.end();
         }
-        self.designator = Some(None);
-        None
-    }
-}
-
-enum LocatorDesignatorType {
-    Number,
-    NumberExtension,
-    Number2ndExtension,
-    PostalDeliveryIdentifier,
-}
-
-struct AdminUnitNameBuilder {
-    name: Option<String>,
-}
-
-struct AddressAreaNameBuilder {
-    name: Option<String>,
-    situated_in_ref: Option<String>,
-}
-
-struct ThoroughfareNameBuilder {
-    name: Option<String>,
-    situated_in_ref: Option<String>,
-}
-
-impl FeatureMemberBuilder {
-    fn new_from_tag(tag: &str) -> Self {
-        match tag {
-            "ad:Address" => FeatureMemberBuilder::Address(AddressBuilder {
-                number: None,
-                number_extension: None,
-                number_2nd_extension: None,
-                postal_delivery_identifier: None,
-                admin_unit_ref: None,
-                address_area_ref: None,
-                thoroughfare_ref: None,
-                locator_designator_builder: None,
-            }),
-            "ad:AdminUnitName" => {
-                FeatureMemberBuilder::AdminUnitName(AdminUnitNameBuilder { name: None })
-            }
-            "ad:AddressAreaName" => FeatureMemberBuilder::AddressAreaName(AddressAreaNameBuilder {
-                name: None,
-                situated_in_ref: None,
-            }),
-            "ad:ThoroughfareName" => {
-                FeatureMemberBuilder::ThoroughfareName(ThoroughfareNameBuilder {
-                    name: None,
-                    situated_in_ref: None,
-                })
-            }
-            _ => panic!("Unknown feature member tag: {}", tag),
-        }
-    }
-}
-
-struct CurrentMemberBuilder {
-    local_id: Option<String>,
-    feature_member: FeatureMemberBuilder,
-}
-
-impl CurrentMemberBuilder {
-    fn visit_start(
-        &mut self,
-        string_interner: &mut StringInterner,
-        current_path: &[StrRef],
-        e: quick_xml::events::BytesStart<'_>,
-    ) {
-        match &mut self.feature_member {
-            FeatureMemberBuilder::AdminUnitName(_) => {
-                // Don't need to check anything here
-            }
-            FeatureMemberBuilder::Address(builder) => {
-                // Check for xlink:href attributes in reference elements
-                // Extract admin_unit_ref, address_area_ref, thoroughfare_ref
-
-                static COMPONENT_PATH: OnceLock<XmlPath> = OnceLock::new();
-                let component_path = COMPONENT_PATH.get_or_init(|| {
-                    vec![
-                        string_interner.intern("gml:FeatureCollection"),
-                        string_interner.intern("gml:featureMember"),
-                        string_interner.intern("ad:Address"),
-                        string_interner.intern("ad:component"),
-                    ]
-                });
-
-                if current_path == component_path {
-                    for attr in e.attributes().flatten() {
-                        if attr.key.as_ref() == b"xlink:href" {
-                            let value = String::from_utf8_lossy(&attr.value);
-                            if value.starts_with("#nl-imbag-ad-adminunitname.") {
-                                builder.admin_unit_ref = Some(
-                                    value
-                                        .strip_prefix("#nl-imbag-ad-adminunitname.")
-                                        .unwrap()
-                                        .to_string(),
-                                );
-                            } else if value.starts_with("#nl-imbag-ad-addressareaname.") {
-                                builder.address_area_ref = Some(
-                                    value
-                                        .strip_prefix("#nl-imbag-ad-addressareaname.")
-                                        .unwrap()
-                                        .to_string(),
-                                );
-                            } else if value.starts_with("#nl-imbag-ad-thoroughfarename.") {
-                                builder.thoroughfare_ref = Some(
-                                    value
-                                        .strip_prefix("#nl-imbag-ad-thoroughfarename.")
-                                        .unwrap()
-                                        .to_string(),
-                                );
-                            }
-                        }
-                    }
-                }
-
-                static DESIGNATOR_PATH: OnceLock<XmlPath> = OnceLock::new();
-                let designator_path = DESIGNATOR_PATH.get_or_init(|| {
-                    vec![
-                        string_interner.intern("gml:FeatureCollection"),
-                        string_interner.intern("gml:featureMember"),
-                        string_interner.intern("ad:Address"),
-                        string_interner.intern("ad:locator"),
-                        string_interner.intern("ad:AddressLocator"),
-                        string_interner.intern("ad:designator"),
-                        string_interner.intern("ad:LocatorDesignator"),
-                        string_interner.intern("ad:type"),
-                    ]
-                });
-
-                if current_path == designator_path {
-                    for attr in e.attributes().flatten() {
-                        if attr.key.as_ref() == b"xlink:href" {
-                            let value = String::from_utf8_lossy(&attr.value);
-                            let type_ = if value
-                                == "http://inspire.ec.europa.eu/codelist/LocatorDesignatorTypeValue/addressNumber"
-                            {
-                                Some(LocatorDesignatorType::Number)
-                            } else if value
-                                == "http://inspire.ec.europa.eu/codelist/LocatorDesignatorTypeValue/addressNumberExtension"
-                            {
-                                Some(LocatorDesignatorType::NumberExtension)
-                            } else if value
-                                == "http://inspire.ec.europa.eu/codelist/LocatorDesignatorTypeValue/addressNumber2ndExtension"
-                            {
-                                Some(LocatorDesignatorType::Number2ndExtension)
-                            } else if value
-                                == "http://inspire.ec.europa.eu/codelist/LocatorDesignatorTypeValue/postalDeliveryIdentifier"
-                            {
-                                Some(LocatorDesignatorType::PostalDeliveryIdentifier)
-                            } else {
-                                None
-                            };
-                            if let Some(type_) = type_ {
-                                if let Some(mut locator_designator) =
-                                    builder.locator_designator_builder.take()
-                                {
-                                    if let Some(designator) = locator_designator.set_type(type_) {
-                                        builder.set_designator(designator);
-                                    } else {
-                                        builder.locator_designator_builder =
-                                            Some(locator_designator);
-                                    }
-                                } else {
-                                    builder.locator_designator_builder =
-                                        Some(LocatorDesignatorBuilder::new_with_type(type_));
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            FeatureMemberBuilder::AddressAreaName(builder) => {
-                static SITUATED_IN_PATH: OnceLock<XmlPath> = OnceLock::new();
-                let situated_in_path = SITUATED_IN_PATH.get_or_init(|| {
-                    vec![
-                        string_interner.intern("gml:FeatureCollection"),
-                        string_interner.intern("gml:featureMember"),
-                        string_interner.intern("ad:AddressAreaName"),
-                        string_interner.intern("ad:situatedWithin"),
-                    ]
-                });
-                if current_path == situated_in_path {
-                    for attr in e.attributes().flatten() {
-                        if attr.key.as_ref() == b"xlink:href" {
-                            let value = String::from_utf8_lossy(&attr.value);
-                            builder.situated_in_ref = Some(
-                                value
-                                    .strip_prefix("#nl-imbag-ad-adminunitname.")
-                                    .unwrap()
-                                    .to_string(),
-                            );
-                        }
-                    }
-                }
-            }
-            FeatureMemberBuilder::ThoroughfareName(builder) => {
-                static SITUATED_IN_PATH: OnceLock<XmlPath> = OnceLock::new();
-                let situated_in_path = SITUATED_IN_PATH.get_or_init(|| {
-                    vec![
-                        string_interner.intern("gml:FeatureCollection"),
-                        string_interner.intern("gml:featureMember"),
-                        string_interner.intern("ad:ThoroughfareName"),
-                        string_interner.intern("ad:situatedWithin"),
-                    ]
-                });
-                if current_path == situated_in_path {
-                    for attr in e.attributes().flatten() {
-                        if attr.key.as_ref() == b"xlink:href" {
-                            let value = String::from_utf8_lossy(&attr.value);
-                            builder.situated_in_ref = Some(
-                                value
-                                    .strip_prefix("#nl-imbag-ad-addressareaname-")
-                                    .unwrap()
-                                    .to_string(),
-                            );
-                        }
-                    }
-                }
+        let mapped = if mmap {
+            source::map_uncompressed(Path::new(&file_path), encoding)?
+        } else {
+            None
+        };
+        if let Some(mapped) = mapped {
+            let mut parser = Parser::new(mapped.bytes())
+                .with_mode(mode)
+                .with_selection(selection.clone());
+            if let Some(rejects) = &rejects {
+                parser = parser.with_rejects(std::io::BufWriter::new(rejects.try_clone()?));
             }
-        }
-    }
-
-    fn visit_end(
-        &mut self,
-        _: &mut StringInterner,
-        _: &[StrRef],
-        _: quick_xml::events::BytesEnd<'_>,
-    ) {
-        // Nothing to do for end tags in this implementation
-    }
-
-    fn visit_empty(
-        &mut self,
-        string_interner: &mut StringInterner,
-        current_path: &[StrRef],
-        e: quick_xml::events::BytesStart<'_>,
-    ) {
-        match &mut self.feature_member {
-            FeatureMemberBuilder::Address(builder) => {
-                // Special handling for some empty tags
-                let designator_path =
-                    DESIGNATOR_PATH.get_or_init(init_designator_path(string_interner));
-                if current_path == designator_path {
-                    // Null designator
-                    if let Some(mut locator_designator) = builder.locator_designator_builder.take()
-                    {
-                        if let Some(designator) = locator_designator.set_designator_none() {
-                            builder.set_designator(designator);
-                        } else {
-                            builder.locator_designator_builder = Some(locator_designator);
-                        }
-                    } else {
-                        builder.locator_designator_builder =
-                            Some(LocatorDesignatorBuilder::new_with_none_designator());
-                    }
-                }
+            let mut feature_members = parser.into_borrowed_iter();
+            for feature_member in &mut feature_members {
+                emitter.emit(feature_member?)?;
             }
-            _ => {}
+            skipped.clone_from(feature_members.skipped());
+            return emitter.end();
         }
 
-        // Handle empty tags - typically reference elements
-        self.visit_start(string_interner, current_path, e);
-    }
-
-    fn visit_text(
-        &mut self,
-        string_interner: &mut StringInterner,
-        current_path: &[StrRef],
-        e: quick_xml::events::BytesText<'_>,
-    ) {
-        // This is where you'll add specific checks for different XML paths
-        match &mut self.feature_member {
-            FeatureMemberBuilder::Address(builder) => {
-                let designator_path =
-                    DESIGNATOR_PATH.get_or_init(init_designator_path(string_interner));
-                if current_path == designator_path {
-                    let text = String::from_utf8(e.into_inner().into_owned()).unwrap();
-                    if let Some(mut locator_designator) = builder.locator_designator_builder.take()
-                    {
-                        if let Some(designator) = locator_designator.set_designator(text) {
-                            builder.set_designator(designator);
-                        } else {
-                            builder.locator_designator_builder = Some(locator_designator);
-                        }
-                    } else {
-                        builder.locator_designator_builder =
-                            Some(LocatorDesignatorBuilder::new_with_designator(text));
-                    }
-                }
+        // The file may be compressed, or a zip archive with several documents
+        source::for_each_document(Path::new(&file_path), encoding, |name, reader| {
+            if let Some(name) = name {
+                eprintln!("Reading {}", name);
             }
-            FeatureMemberBuilder::AdminUnitName(builder) => {
-                static NAME_PATH: OnceLock<XmlPath> = OnceLock::new();
-                let name_path = NAME_PATH.get_or_init(|| {
-                    vec![
-                        string_interner.intern("gml:FeatureCollection"),
-                        string_interner.intern("gml:featureMember"),
-                        string_interner.intern("ad:AdminUnitName"),
-                        string_interner.intern("ad:name"),
-                        string_interner.intern("gn:GeographicalName"),
-                        string_interner.intern("gn:spelling"),
-                        string_interner.intern("gn:SpellingOfName"),
-                        string_interner.intern("gn:text"),
-                    ]
-                });
-                if current_path == name_path && builder.name.is_none() {
-                    let text = String::from_utf8(e.into_inner().into_owned()).unwrap();
-                    builder.name = Some(text);
+            let document_skipped = if let Some(threads) = threads {
+                let mut parser = ParallelParser::new(reader)
+                    .with_mode(mode)
+                    .with_selection(selection.clone())
+                    .with_threads(threads);
+                if let Some(rejects) = &rejects {
+                    parser = parser.with_rejects(std::io::BufWriter::new(rejects.try_clone()?));
                 }
-            }
-            FeatureMemberBuilder::AddressAreaName(builder) => {
-                static NAME_PATH: OnceLock<XmlPath> = OnceLock::new();
-                let name_path = NAME_PATH.get_or_init(|| {
-                    vec![
-                        string_interner.intern("gml:FeatureCollection"),
-                        string_interner.intern("gml:featureMember"),
-                        string_interner.intern("ad:AddressAreaName"),
-                        string_interner.intern("ad:name"),
-                        string_interner.intern("gn:GeographicalName"),
-                        string_interner.intern("gn:spelling"),
-                        string_interner.intern("gn:SpellingOfName"),
-                        string_interner.intern("gn:text"),
-                    ]
-                });
-                if current_path == name_path && builder.name.is_none() {
-                    let text = String::from_utf8(e.into_inner().into_owned()).unwrap();
-                    builder.name = Some(text);
+                parser.for_each(|feature_member| emitter.emit(feature_member))?;
+                parser.skipped().clone()
+            } else {
+                let mut parser = Parser::new(reader)
+                    .with_mode(mode)
+                    .with_selection(selection.clone());
+                if let Some(rejects) = &rejects {
+                    parser = parser.with_rejects(std::io::BufWriter::new(rejects.try_clone()?));
                 }
-            }
-            FeatureMemberBuilder::ThoroughfareName(builder) => {
-                static NAME_PATH: OnceLock<XmlPath> = OnceLock::new();
-                let name_path = NAME_PATH.get_or_init(|| {
-                    vec![
-                        string_interner.intern("gml:FeatureCollection"),
-                        string_interner.intern("gml:featureMember"),
-                        string_interner.intern("ad:ThoroughfareName"),
-                        string_interner.intern("ad:name"),
-                        string_interner.intern("ad:ThoroughfareNameValue"),
-                        string_interner.intern("ad:name"),
-                        string_interner.intern("gn:GeographicalName"),
-                        string_interner.intern("gn:spelling"),
-                        string_interner.intern("gn:SpellingOfName"),
-                        string_interner.intern("gn:text"),
-                    ]
-                });
-                if current_path == name_path && builder.name.is_none() {
-                    let text = String::from_utf8(e.into_inner().into_owned()).unwrap();
-                    builder.name = Some(text);
+                let mut feature_members = parser.into_iter();
+                for feature_member in &mut feature_members {
+                    emitter.emit(feature_member?)?;
                 }
+                feature_members.skipped().clone()
+            };
+            for (tag, count) in document_skipped {
+                *skipped.entry(tag).or_default() += count;
             }
-        }
-    }
-
-    fn finish(self) -> FeatureMember {
-        match self.feature_member {
-            FeatureMemberBuilder::Address(builder) => FeatureMember::Address {
-                local_id: self.local_id.expect("Local ID not set"),
-                number: builder.number,
-                number_extension: builder.number_extension,
-                number_2nd_extension: builder.number_2nd_extension,
-                postal_delivery_identifier: builder.postal_delivery_identifier,
-                admin_unit_ref: builder.admin_unit_ref,
-                address_area_ref: builder.address_area_ref,
-                thoroughfare_ref: builder.thoroughfare_ref,
-            },
-            FeatureMemberBuilder::AdminUnitName(builder) => FeatureMember::AdminUnitName {
-                local_id: self.local_id.expect("Local ID not set"),
-                name: builder.name,
-            },
-            FeatureMemberBuilder::AddressAreaName(builder) => FeatureMember::AddressAreaName {
-                local_id: self.local_id.expect("Local ID not set"),
-                name: builder.name,
-                situated_in_ref: builder.situated_in_ref,
-            },
-            FeatureMemberBuilder::ThoroughfareName(builder) => FeatureMember::ThoroughfareName {
-                local_id: self.local_id.expect("Local ID not set"),
-                name: builder.name,
-                situated_in_ref: builder.situated_in_ref,
-            },
-        }
-    }
-}
-
-struct AddressCollector<T> {
-    string_interner: gmlparser::StringInterner,
-    current_path: XmlPath,
-    current_member: Option<CurrentMemberBuilder>,
-    emitter: T,
-}
-
-static FEATURE_MEMBER_PREFIX: OnceLock<XmlPath> = OnceLock::new();
-
-fn init_feature_member_prefix(interner: &mut StringInterner) -> impl FnOnce() -> XmlPath {
-    move || {
-        vec![
-            interner.intern("gml:FeatureCollection"),
-            interner.intern("gml:featureMember"),
-        ]
-    }
-}
-
-static DESIGNATOR_PATH: OnceLock<XmlPath> = OnceLock::new();
-
-fn init_designator_path(interner: &mut StringInterner) -> impl FnOnce() -> XmlPath {
-    move || {
-        vec![
-            interner.intern("gml:FeatureCollection"),
-            interner.intern("gml:featureMember"),
-            interner.intern("ad:Address"),
-            interner.intern("ad:locator"),
-            interner.intern("ad:AddressLocator"),
-            interner.intern("ad:designator"),
-            interner.intern("ad:LocatorDesignator"),
-            interner.intern("ad:designator"),
-        ]
+            Ok(())
+        })?;
+        emitter.end()
+    })();
+    if let Err(error) = result {
+        eprintln!("{}", error);
+        std::process::exit(1);
     }
-}
-
-impl<T> AddressCollector<T>
-where
-    T: FeatureMemberEmitter,
-{
-    fn new(mut emitter: T) -> Self {
-        emitter.start();
-        AddressCollector {
-            string_interner: gmlparser::StringInterner::default(),
-            current_path: Vec::new(),
-            current_member: None,
-            emitter,
-        }
+    for (tag, count) in skipped {
+        eprintln!("Skipped {} feature members of unknown type {}", count, tag);
     }
 
-    fn visit_start(&mut self, e: quick_xml::events::BytesStart) {
-        let feature_member_tag = FEATURE_MEMBER_PREFIX
-            .get_or_init(init_feature_member_prefix(&mut self.string_interner));
-
-        let name_ref = self
-            .string_interner
-            .intern(str::from_utf8(e.name().as_ref()).unwrap());
-        self.current_path.push(name_ref);
-
-        if self.current_path == *feature_member_tag || self.current_path.len() < 2 {
-            assert!(self.current_member.is_none());
-        } else if path_starts_with(&self.current_path, feature_member_tag)
-            && self.current_path.len() == feature_member_tag.len() + 1
-        {
-            // A new member is starting
-            let tag = self.string_interner.get(name_ref);
-            let feature_member = FeatureMemberBuilder::new_from_tag(tag);
-            self.current_member = Some(CurrentMemberBuilder {
-                local_id: None,
-                feature_member,
-            });
-        } else {
-            assert!(self.current_member.is_some());
-            self.current_member.as_mut().unwrap().visit_start(
-                &mut self.string_interner,
-                &self.current_path,
-                e,
-            );
-        }
-    }
-
-    fn visit_end(&mut self, e: quick_xml::events::BytesEnd) {
-        let feature_member_tag = FEATURE_MEMBER_PREFIX
-            .get_or_init(init_feature_member_prefix(&mut self.string_interner));
-
-        let name_ref = self
-            .string_interner
-            .intern(str::from_utf8(e.name().as_ref()).unwrap());
-        if self.current_path.last() == Some(&name_ref) {
-            self.current_path.pop();
-        } else {
-            panic!("Mismatched end tag: {:?}", name_ref);
-        }
-
-        if self.current_member.is_none() {
-            assert!(self.current_path.len() <= 1);
-            return;
+    if let ChooseEmitter::Validation(emitter) = &emitter
+        && let Some(report) = emitter.report()
+    {
+        if let Err(error) = report.write_json(&mut std::io::stdout().lock()) {
+            eprintln!("Unable to write the report: {}", error);
+            std::process::exit(1);
         }
-
-        if self.current_path == *feature_member_tag {
-            let current_member = self.current_member.take().unwrap();
-            let finished_member = current_member.finish();
-            self.emitter.emit(finished_member);
-        } else {
-            self.current_member.as_mut().unwrap().visit_end(
-                &mut self.string_interner,
-                &self.current_path,
-                e,
-            );
+        if !report_only && !report.is_valid() {
+            std::process::exit(1);
         }
     }
-
-    fn visit_text(&mut self, e: quick_xml::events::BytesText) {
-        static LOCAL_ID_SUFFIX: OnceLock<XmlPath> = OnceLock::new();
-        let local_id_suffix = LOCAL_ID_SUFFIX.get_or_init(|| {
-            vec![
-                self.string_interner.intern("ad:inspireId"),
-                self.string_interner.intern("base:Identifier"),
-                self.string_interner.intern("base:localId"),
-            ]
-        });
-
-        assert!(self.current_member.is_some());
-
-        let current_member = self.current_member.as_mut().unwrap();
-        if path_ends_with(&self.current_path, local_id_suffix) {
-            current_member.local_id = Some(String::from_utf8(e.into_inner().into_owned()).unwrap());
-            return;
-        }
-
-        current_member.visit_text(&mut self.string_interner, &self.current_path, e);
-    }
-
-    fn visit_empty(&mut self, e: quick_xml::events::BytesStart<'_>) {
-        let name_ref = self
-            .string_interner
-            .intern(str::from_utf8(e.name().as_ref()).unwrap());
-        self.current_path.push(name_ref);
-
-        assert!(self.current_member.is_some());
-
-        self.current_member.as_mut().unwrap().visit_empty(
-            &mut self.string_interner,
-            &self.current_path,
-            e,
-        );
-
-        self.current_path.pop();
-    }
-
-    fn end(mut self) {
-        self.emitter.end();
-    }
 }
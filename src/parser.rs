@@ -0,0 +1,1379 @@
+use core::str;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+
+use quick_xml::{
+    events::Event,
+    name::{QName, ResolveResult},
+    reader::NsReader,
+};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+mod borrowed;
+mod events;
+mod parallel;
+mod reference;
+mod selection;
+
+pub use borrowed::BorrowedFeatureMemberIter;
+pub(crate) use events::line_breaks;
+use events::{EventSource, ReaderEvents};
+pub use parallel::ParallelParser;
+pub(crate) use reference::ReferenceResolver;
+pub use selection::{FeatureMemberType, Field, Selection};
+
+use crate::{
+    Designator, FeatureMember, GeographicalName, Identifier, Lifecycle, Locator, LocatorName,
+    Position, Spelling, StrRef, StringInterner,
+    emitter::FeatureMemberEmitter,
+    error::{Error, ParseErrorKind, Result},
+};
+
+/// Parses an INSPIRE addresses GML document.
+///
+/// Feature members can either be pushed into an emitter with [`Parser::parse`], or pulled one at a
+/// time by iterating over the parser.
+pub struct Parser<R> {
+    xml_reader: NsReader<R>,
+    mode: Mode,
+    selection: Selection,
+    rejects: Option<Box<dyn Write>>,
+}
+
+/// How the parser treats feature members of a type it doesn't know.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Fail with [`ParseErrorKind::UnknownFeatureMember`].
+    #[default]
+    Strict,
+    /// Skip them, counting them per tag in [`FeatureMemberIter::skipped`].
+    Lenient,
+}
+
+impl<R> Parser<R>
+where
+    R: BufRead,
+{
+    pub fn new(reader: R) -> Self {
+        Parser {
+            xml_reader: NsReader::from_reader(reader),
+            mode: Mode::default(),
+            selection: Selection::all(),
+            rejects: None,
+        }
+    }
+
+    pub fn with_mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Only reads the feature members and properties in `selection`, skipping the rest.
+    pub fn with_selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// Writes the raw XML of every feature member skipped in lenient mode to `rejects`, one per
+    /// line. Namespace prefixes are kept as they are in the document, so the fragments are not
+    /// namespace-well-formed on their own.
+    pub fn with_rejects(mut self, rejects: impl Write + 'static) -> Self {
+        self.rejects = Some(Box::new(rejects));
+        self
+    }
+
+    /// Reads the whole document, emitting each feature member as soon as it is complete.
+    ///
+    /// The emitter is returned after its `end` has been called. Parsing stops at the first error
+    /// from either the document or the emitter, in which case `end` is not called.
+    pub fn parse<T>(self, emitter: T) -> Result<T>
+    where
+        T: FeatureMemberEmitter,
+    {
+        self.into_iter().emit_all(emitter)
+    }
+}
+
+impl<R> IntoIterator for Parser<R>
+where
+    R: BufRead,
+{
+    type Item = Result<FeatureMember<'static>>;
+    type IntoIter = FeatureMemberIter<R>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        FeatureMembers::with_source(
+            ReaderEvents::new(self.xml_reader),
+            self.mode,
+            self.selection,
+            self.rejects,
+        )
+    }
+}
+
+/// Pulls feature members out of a GML document one at a time.
+///
+/// The document is only read as far as needed to complete the next feature member, so dropping
+/// the iterator early stops reading the input. After an error has been returned the iterator is
+/// exhausted.
+pub type FeatureMemberIter<R> = FeatureMembers<'static, ReaderEvents<R>>;
+
+/// Pulls feature members out of a document read from `S`, whose text lives for `'a`. See
+/// [`FeatureMemberIter`] and [`BorrowedFeatureMemberIter`].
+pub struct FeatureMembers<'a, S> {
+    source: S,
+    collector: AddressCollector<'a>,
+    rejects: Option<quick_xml::Writer<Box<dyn Write>>>,
+    skipped: BTreeMap<String, u64>,
+    done: bool,
+}
+
+impl<R> FeatureMemberIter<R>
+where
+    R: BufRead,
+{
+    pub fn new(xml_reader: NsReader<R>) -> Self {
+        FeatureMembers::with_source(
+            ReaderEvents::new(xml_reader),
+            Mode::default(),
+            Selection::all(),
+            None,
+        )
+    }
+}
+
+impl<'a, S> FeatureMembers<'a, S>
+where
+    S: EventSource<'a>,
+{
+    fn with_source(
+        source: S,
+        mode: Mode,
+        selection: Selection,
+        rejects: Option<Box<dyn Write>>,
+    ) -> Self {
+        let mut collector = AddressCollector::new();
+        collector.mode = mode;
+        collector.select(selection);
+        FeatureMembers {
+            source,
+            collector,
+            rejects: rejects.map(quick_xml::Writer::new),
+            skipped: BTreeMap::new(),
+            done: false,
+        }
+    }
+
+    /// Emits all remaining feature members, like [`Parser::parse`] does, but leaves the iterator
+    /// to be inspected afterwards.
+    pub fn emit_all<T>(&mut self, mut emitter: T) -> Result<T>
+    where
+        T: FeatureMemberEmitter,
+    {
+        emitter.start()?;
+        for feature_member in self.by_ref() {
+            emitter.emit(feature_member?)?;
+        }
+        emitter.end()?;
+        Ok(emitter)
+    }
+
+    /// Returns how many feature members have been skipped in lenient mode, per tag in Clark
+    /// notation.
+    pub fn skipped(&self) -> &BTreeMap<String, u64> {
+        &self.skipped
+    }
+
+    /// Returns the underlying XML reader, for example to inspect its position.
+    pub fn get_ref(&self) -> &NsReader<S::Reader> {
+        self.source.xml_reader()
+    }
+
+    /// Resolves references to the features registered in `resolver` as well, as if the document
+    /// continued another one, such as the previous page of a WFS response.
+    pub(crate) fn with_resolver(mut self, resolver: ReferenceResolver) -> Self {
+        self.collector.resolver = resolver;
+        self
+    }
+
+    /// Returns how many feature members have been left out by the selection.
+    pub(crate) fn unselected(&self) -> u64 {
+        self.collector.unselected
+    }
+
+    /// Takes the features registered so far, to resolve references to them in a document that
+    /// continues this one.
+    pub(crate) fn take_resolver(&mut self) -> ReferenceResolver {
+        std::mem::take(&mut self.collector.resolver)
+    }
+
+    /// Reads a single event, returning the feature member it completes, if any.
+    fn read_event(&mut self) -> Result<Option<FeatureMember<'a>>> {
+        let (event, xml_reader) = self.source.read_event(&mut self.collector)?;
+        match event {
+            Event::Start(e) => match self.collector.visit_start(xml_reader, e.borrow())? {
+                Some(Skip::Unknown(tag)) => {
+                    *self.skipped.entry(tag).or_default() += 1;
+                    if let Some(rejects) = self.rejects.as_mut() {
+                        rejects.write_event(Event::Start(e))?;
+                    }
+                    self.skip_element(true)?;
+                }
+                Some(Skip::Unselected | Skip::Unrelated) => self.skip_element(false)?,
+                None => {}
+            },
+            // An empty element is a start tag and an end tag in one
+            Event::Empty(e) => match self.collector.visit_start(xml_reader, e.borrow())? {
+                Some(Skip::Unknown(tag)) => {
+                    *self.skipped.entry(tag).or_default() += 1;
+                    if let Some(rejects) = self.rejects.as_mut() {
+                        rejects.write_event(Event::Empty(e))?;
+                        rejects.get_mut().write_all(b"\n")?;
+                    }
+                }
+                Some(Skip::Unselected | Skip::Unrelated) => {}
+                None => return self.collector.visit_end(xml_reader, e.to_end()),
+            },
+            Event::End(e) => return self.collector.visit_end(xml_reader, e),
+            Event::Eof => {
+                self.done = true;
+                if let Some(rejects) = self.rejects.as_mut() {
+                    rejects.get_mut().flush()?;
+                }
+            }
+            // Text has been passed to the collector by the source
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    /// Reads past the end of the element that has just started, writing it to the rejects if
+    /// it is `rejected` and there are any.
+    fn skip_element(&mut self, rejected: bool) -> Result<()> {
+        let mut rejects = self.rejects.as_mut().filter(|_| rejected);
+        let mut depth = 1;
+        while depth > 0 {
+            let event = self.source.skip_event()?;
+            match event {
+                Event::Start(_) => depth += 1,
+                Event::End(_) => depth -= 1,
+                Event::Eof => break,
+                _ => {}
+            }
+            if let Some(rejects) = rejects.as_mut() {
+                rejects.write_event(event)?;
+            }
+        }
+        if let Some(rejects) = rejects {
+            rejects.get_mut().write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, S> Iterator for FeatureMembers<'a, S>
+where
+    S: EventSource<'a>,
+{
+    type Item = Result<FeatureMember<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.read_event() {
+                Ok(Some(feature_member)) => return Some(Ok(feature_member)),
+                Ok(None) => {}
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<'a, S> std::iter::FusedIterator for FeatureMembers<'a, S> where S: EventSource<'a> {}
+
+const GML_NAMESPACE: &str = "http://www.opengis.net/gml/3.2";
+const AD_NAMESPACE: &str = "http://inspire.ec.europa.eu/schemas/ad/4.0";
+const BASE_NAMESPACE: &str = "http://inspire.ec.europa.eu/schemas/base/3.3";
+const GN_NAMESPACE: &str = "http://inspire.ec.europa.eu/schemas/gn/4.0";
+const XLINK_NAMESPACE: &str = "http://www.w3.org/1999/xlink";
+const WFS_NAMESPACE: &str = "http://www.opengis.net/wfs/2.0";
+
+/// Element names are interned in Clark notation, `{namespace}localName`, so they match no matter
+/// which prefix a document binds a namespace to.
+type XmlPath = Vec<StrRef>;
+
+/// Expands a name written with one of the conventional prefixes, e.g. `ad:Address`, to Clark
+/// notation.
+fn expand_name(name: &str) -> String {
+    let (prefix, local_name) = name.split_once(':').expect("name has a prefix");
+    let namespace = match prefix {
+        "gml" => GML_NAMESPACE,
+        "ad" => AD_NAMESPACE,
+        "base" => BASE_NAMESPACE,
+        "gn" => GN_NAMESPACE,
+        "wfs" => WFS_NAMESPACE,
+        _ => panic!("unknown prefix {}", prefix),
+    };
+    format!("{{{}}}{}", namespace, local_name)
+}
+
+fn path_starts_with(path: &[StrRef], prefix: &[StrRef]) -> bool {
+    if path.len() < prefix.len() {
+        return false;
+    }
+    prefix.iter().zip(path.iter()).all(|(a, b)| a == b)
+}
+
+fn path_ends_with(path: &XmlPath, suffix: &XmlPath) -> bool {
+    if path.len() < suffix.len() {
+        return false;
+    }
+    suffix
+        .iter()
+        .rev()
+        .zip(path.iter().rev())
+        .all(|(a, b)| a == b)
+}
+
+/// The element paths the builders are interested in, starting at the feature element, e.g.
+/// `ad:Address`, wherever its container puts it.
+///
+/// These are interned in the interner of the collector that owns them, so a path can only be
+/// compared against paths built by that same collector.
+struct Paths {
+    /// Properties whose children are feature members: `gml:featureMember`, `gml:featureMembers`
+    /// and `wfs:member`.
+    member_properties: Vec<StrRef>,
+    /// Elements that hold feature members without being one: the member properties, feature
+    /// collections and `wfs:additionalObjects`.
+    containers: Vec<StrRef>,
+    local_id_suffix: XmlPath,
+    version_id_suffix: XmlPath,
+    namespace_suffix: XmlPath,
+    // Life-cycle properties, which are direct children of every feature
+    status: StrRef,
+    valid_from: StrRef,
+    valid_to: StrRef,
+    begin_lifespan_version: StrRef,
+    end_lifespan_version: StrRef,
+    // Properties that a selection can leave out
+    address_position_property: XmlPath,
+    address_locator_property: XmlPath,
+    name_properties: Vec<XmlPath>,
+    address_component: XmlPath,
+    address_parent_address: XmlPath,
+    address_building: XmlPath,
+    // An `ad:AddressLocator`, and paths relative to it
+    address_locator: XmlPath,
+    locator_level: XmlPath,
+    locator_designator: XmlPath,
+    locator_designator_text: XmlPath,
+    locator_designator_type: XmlPath,
+    locator_name: XmlPath,
+    locator_name_type: XmlPath,
+    /// The `gn:GeographicalName`s of the last `ad:LocatorName`
+    address_locator_names: XmlPath,
+    address_position: XmlPath,
+    address_position_pos: XmlPath,
+    address_position_method: XmlPath,
+    address_position_specification: XmlPath,
+    address_position_default: XmlPath,
+    // The `gn:GeographicalName`s of a feature, and paths relative to them
+    admin_unit_names: XmlPath,
+    address_area_names: XmlPath,
+    address_area_situated_in: XmlPath,
+    thoroughfare_names: XmlPath,
+    thoroughfare_situated_in: XmlPath,
+    name_language: XmlPath,
+    name_status: XmlPath,
+    name_spelling: XmlPath,
+    name_spelling_text: XmlPath,
+    name_spelling_script: XmlPath,
+    postal_descriptor_post_code: XmlPath,
+    postal_descriptor_post_name: XmlPath,
+    postal_descriptor_situated_in: XmlPath,
+}
+
+impl Paths {
+    fn new(interner: &mut StringInterner) -> Self {
+        let status = interner.intern(&expand_name("ad:status"));
+        let valid_from = interner.intern(&expand_name("ad:validFrom"));
+        let valid_to = interner.intern(&expand_name("ad:validTo"));
+        let begin_lifespan_version = interner.intern(&expand_name("ad:beginLifespanVersion"));
+        let end_lifespan_version = interner.intern(&expand_name("ad:endLifespanVersion"));
+        let mut path = |tags: &[&str]| -> XmlPath {
+            tags.iter()
+                .map(|tag| interner.intern(&expand_name(tag)))
+                .collect()
+        };
+
+        let member_properties = path(&["gml:featureMember", "gml:featureMembers", "wfs:member"]);
+        let mut containers = path(&[
+            "gml:FeatureCollection",
+            "wfs:FeatureCollection",
+            "wfs:SimpleFeatureCollection",
+            "wfs:additionalObjects",
+        ]);
+        containers.extend(&member_properties);
+        Paths {
+            member_properties,
+            containers,
+            local_id_suffix: path(&["ad:inspireId", "base:Identifier", "base:localId"]),
+            version_id_suffix: path(&["ad:inspireId", "base:Identifier", "base:versionId"]),
+            namespace_suffix: path(&["ad:inspireId", "base:Identifier", "base:namespace"]),
+            status,
+            valid_from,
+            valid_to,
+            begin_lifespan_version,
+            end_lifespan_version,
+            address_position_property: path(&["ad:Address", "ad:position"]),
+            address_locator_property: path(&["ad:Address", "ad:locator"]),
+            name_properties: vec![
+                path(&["ad:AdminUnitName", "ad:name"]),
+                path(&["ad:AddressAreaName", "ad:name"]),
+                path(&["ad:ThoroughfareName", "ad:name"]),
+                path(&["ad:PostalDescriptor", "ad:postName"]),
+            ],
+            address_component: path(&["ad:Address", "ad:component"]),
+            address_parent_address: path(&["ad:Address", "ad:parentAddress"]),
+            address_building: path(&["ad:Address", "ad:building"]),
+            address_locator: path(&["ad:Address", "ad:locator", "ad:AddressLocator"]),
+            locator_level: path(&["ad:level"]),
+            locator_designator: path(&["ad:designator", "ad:LocatorDesignator"]),
+            locator_designator_text: path(&[
+                "ad:designator",
+                "ad:LocatorDesignator",
+                "ad:designator",
+            ]),
+            locator_designator_type: path(&["ad:designator", "ad:LocatorDesignator", "ad:type"]),
+            locator_name: path(&["ad:name", "ad:LocatorName"]),
+            locator_name_type: path(&["ad:name", "ad:LocatorName", "ad:type"]),
+            address_locator_names: path(&[
+                "ad:Address",
+                "ad:locator",
+                "ad:AddressLocator",
+                "ad:name",
+                "ad:LocatorName",
+                "ad:name",
+                "gn:GeographicalName",
+            ]),
+            address_position: path(&["ad:Address", "ad:position", "ad:GeographicPosition"]),
+            address_position_pos: path(&[
+                "ad:Address",
+                "ad:position",
+                "ad:GeographicPosition",
+                "ad:geometry",
+                "gml:Point",
+                "gml:pos",
+            ]),
+            address_position_method: path(&[
+                "ad:Address",
+                "ad:position",
+                "ad:GeographicPosition",
+                "ad:method",
+            ]),
+            address_position_specification: path(&[
+                "ad:Address",
+                "ad:position",
+                "ad:GeographicPosition",
+                "ad:specification",
+            ]),
+            address_position_default: path(&[
+                "ad:Address",
+                "ad:position",
+                "ad:GeographicPosition",
+                "ad:default",
+            ]),
+            admin_unit_names: path(&["ad:AdminUnitName", "ad:name", "gn:GeographicalName"]),
+            address_area_names: path(&["ad:AddressAreaName", "ad:name", "gn:GeographicalName"]),
+            address_area_situated_in: path(&["ad:AddressAreaName", "ad:situatedWithin"]),
+            thoroughfare_names: path(&[
+                "ad:ThoroughfareName",
+                "ad:name",
+                "ad:ThoroughfareNameValue",
+                "ad:name",
+                "gn:GeographicalName",
+            ]),
+            thoroughfare_situated_in: path(&["ad:ThoroughfareName", "ad:situatedWithin"]),
+            name_language: path(&["gn:language"]),
+            name_status: path(&["gn:nameStatus"]),
+            name_spelling: path(&["gn:spelling", "gn:SpellingOfName"]),
+            name_spelling_text: path(&["gn:spelling", "gn:SpellingOfName", "gn:text"]),
+            name_spelling_script: path(&["gn:spelling", "gn:SpellingOfName", "gn:script"]),
+            postal_descriptor_post_code: path(&["ad:PostalDescriptor", "ad:postCode"]),
+            postal_descriptor_post_name: path(&[
+                "ad:PostalDescriptor",
+                "ad:postName",
+                "gn:GeographicalName",
+                "gn:spelling",
+                "gn:SpellingOfName",
+                "gn:text",
+            ]),
+            postal_descriptor_situated_in: path(&["ad:PostalDescriptor", "ad:situatedWithin"]),
+        }
+    }
+
+    /// Returns the properties that `field` is made of.
+    fn field_properties(&self, field: Field) -> Vec<XmlPath> {
+        match field {
+            Field::Position => vec![self.address_position_property.clone()],
+            Field::Locators => vec![self.address_locator_property.clone()],
+            Field::Names => self.name_properties.clone(),
+        }
+    }
+
+    /// Whether the current element is the property `name` of the feature being built.
+    fn is_feature_property(&self, current_path: &[StrRef], name: StrRef) -> bool {
+        current_path.len() == 2 && current_path.last() == Some(&name)
+    }
+}
+
+enum FeatureMemberBuilder<'a> {
+    Address(Box<AddressBuilder<'a>>),
+    AdminUnitName(AdminUnitNameBuilder<'a>),
+    AddressAreaName(AddressAreaNameBuilder<'a>),
+    ThoroughfareName(ThoroughfareNameBuilder<'a>),
+    PostalDescriptor(PostalDescriptorBuilder<'a>),
+}
+
+struct AddressBuilder<'a> {
+    /// The `xlink:href` of every `ad:component`, resolved when the address is finished.
+    components: Vec<String>,
+    parent_address: Option<String>,
+    buildings: Vec<String>,
+    locators: Vec<Locator<'a>>,
+    position: Option<Position>,
+    position_builder: Option<PositionBuilder>,
+}
+
+#[derive(Default)]
+struct PositionBuilder {
+    pos: Option<(f64, f64)>,
+    method: Option<String>,
+    specification: Option<String>,
+    default: bool,
+}
+
+impl PositionBuilder {
+    fn finish(self) -> Option<Position> {
+        let (x, y) = self.pos?;
+        Some(Position {
+            x,
+            y,
+            method: self.method,
+            specification: self.specification,
+            default: self.default,
+        })
+    }
+}
+
+struct AdminUnitNameBuilder<'a> {
+    names: Vec<GeographicalName<'a>>,
+}
+
+struct AddressAreaNameBuilder<'a> {
+    names: Vec<GeographicalName<'a>>,
+    situated_in_ref: Option<String>,
+}
+
+struct ThoroughfareNameBuilder<'a> {
+    names: Vec<GeographicalName<'a>>,
+    situated_in_ref: Option<String>,
+}
+
+struct PostalDescriptorBuilder<'a> {
+    post_code: Option<Cow<'a, str>>,
+    post_name: Option<Cow<'a, str>>,
+    admin_unit_ref: Option<String>,
+}
+
+impl FeatureMemberType {
+    fn from_tag(tag: &str) -> Result<Self, ParseErrorKind> {
+        tag.strip_prefix('{')
+            .and_then(|tag| tag.strip_prefix(AD_NAMESPACE))
+            .and_then(|tag| tag.strip_prefix('}'))
+            .and_then(FeatureMemberType::from_local_name)
+            .ok_or_else(|| ParseErrorKind::UnknownFeatureMember(tag.to_string()))
+    }
+}
+
+impl FeatureMemberBuilder<'_> {
+    fn new(feature_type: FeatureMemberType) -> Self {
+        match feature_type {
+            FeatureMemberType::Address => FeatureMemberBuilder::Address(Box::new(AddressBuilder {
+                components: Vec::new(),
+                parent_address: None,
+                buildings: Vec::new(),
+                locators: Vec::new(),
+                position: None,
+                position_builder: None,
+            })),
+            FeatureMemberType::AdminUnitName => {
+                FeatureMemberBuilder::AdminUnitName(AdminUnitNameBuilder { names: Vec::new() })
+            }
+            FeatureMemberType::AddressAreaName => {
+                FeatureMemberBuilder::AddressAreaName(AddressAreaNameBuilder {
+                    names: Vec::new(),
+                    situated_in_ref: None,
+                })
+            }
+            FeatureMemberType::ThoroughfareName => {
+                FeatureMemberBuilder::ThoroughfareName(ThoroughfareNameBuilder {
+                    names: Vec::new(),
+                    situated_in_ref: None,
+                })
+            }
+            FeatureMemberType::PostalDescriptor => {
+                FeatureMemberBuilder::PostalDescriptor(PostalDescriptorBuilder {
+                    post_code: None,
+                    post_name: None,
+                    admin_unit_ref: None,
+                })
+            }
+        }
+    }
+}
+
+struct CurrentMemberBuilder<'a> {
+    local_id: Option<Cow<'a, str>>,
+    lifecycle: Lifecycle,
+    identifier: Identifier<'a>,
+    feature_member: FeatureMemberBuilder<'a>,
+}
+
+impl<'a> CurrentMemberBuilder<'a> {
+    fn visit_start<R>(
+        &mut self,
+        paths: &Paths,
+        current_path: &[StrRef],
+        reader: &NsReader<R>,
+        e: quick_xml::events::BytesStart<'_>,
+    ) -> Result<(), ParseErrorKind> {
+        if paths.is_feature_property(current_path, paths.status) {
+            self.lifecycle.status = xlink_href(reader, &e).map(|href| codelist_value(&href));
+            return Ok(());
+        }
+
+        if let Some((names_path, names)) = self.names(paths)
+            && path_starts_with(current_path, names_path)
+        {
+            let relative_path = &current_path[names_path.len()..];
+            if relative_path.is_empty() {
+                names.push(GeographicalName::default());
+            } else if let Some(name) = names.last_mut() {
+                if relative_path == paths.name_status {
+                    name.name_status = xlink_href(reader, &e).map(|href| codelist_value(&href));
+                } else if relative_path == paths.name_spelling {
+                    name.spellings.push(Spelling {
+                        text: Cow::Borrowed(""),
+                        script: None,
+                    });
+                }
+            }
+            return Ok(());
+        }
+
+        match &mut self.feature_member {
+            FeatureMemberBuilder::AdminUnitName(_) => {
+                // Don't need to check anything here
+            }
+            FeatureMemberBuilder::Address(builder) => {
+                if current_path == paths.address_component
+                    && let Some(href) = xlink_href(reader, &e)
+                {
+                    builder.components.push(href);
+                } else if current_path == paths.address_parent_address {
+                    builder.parent_address = xlink_href(reader, &e);
+                } else if current_path == paths.address_building
+                    && let Some(href) = xlink_href(reader, &e)
+                {
+                    builder.buildings.push(href);
+                }
+
+                if current_path == paths.address_position {
+                    builder.position_builder = Some(PositionBuilder::default());
+                } else if let Some(position_builder) = builder.position_builder.as_mut() {
+                    if current_path == paths.address_position_method {
+                        position_builder.method =
+                            xlink_href(reader, &e).map(|href| codelist_value(&href));
+                    } else if current_path == paths.address_position_specification {
+                        position_builder.specification =
+                            xlink_href(reader, &e).map(|href| codelist_value(&href));
+                    }
+                }
+
+                if current_path == paths.address_locator {
+                    builder.locators.push(Locator::default());
+                } else if let Some(locator) = builder.locators.last_mut()
+                    && path_starts_with(current_path, &paths.address_locator)
+                {
+                    let relative_path = &current_path[paths.address_locator.len()..];
+                    if relative_path == paths.locator_level {
+                        locator.level = xlink_href(reader, &e).map(|href| codelist_value(&href));
+                    } else if relative_path == paths.locator_designator {
+                        locator.designators.push(Designator::default());
+                    } else if relative_path == paths.locator_designator_type
+                        && let Some(designator) = locator.designators.last_mut()
+                    {
+                        designator.designator_type =
+                            xlink_href(reader, &e).map(|href| codelist_value(&href));
+                    } else if relative_path == paths.locator_name {
+                        locator.names.push(LocatorName::default());
+                    } else if relative_path == paths.locator_name_type
+                        && let Some(name) = locator.names.last_mut()
+                    {
+                        name.name_type = xlink_href(reader, &e).map(|href| codelist_value(&href));
+                    }
+                }
+            }
+            FeatureMemberBuilder::AddressAreaName(builder) => {
+                if current_path == paths.address_area_situated_in {
+                    builder.situated_in_ref = xlink_href(reader, &e);
+                }
+            }
+            FeatureMemberBuilder::ThoroughfareName(builder) => {
+                if current_path == paths.thoroughfare_situated_in {
+                    builder.situated_in_ref = xlink_href(reader, &e);
+                }
+            }
+            FeatureMemberBuilder::PostalDescriptor(builder) => {
+                if current_path == paths.postal_descriptor_situated_in {
+                    builder.admin_unit_ref = xlink_href(reader, &e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_end(
+        &mut self,
+        paths: &Paths,
+        current_path: &[StrRef],
+        _: quick_xml::events::BytesEnd<'_>,
+    ) {
+        if let FeatureMemberBuilder::Address(builder) = &mut self.feature_member
+            && current_path == paths.address_position
+        {
+            // Prefer the default position if an address has more than one
+            let position = builder
+                .position_builder
+                .take()
+                .and_then(PositionBuilder::finish);
+            if let Some(position) = position
+                && builder
+                    .position
+                    .as_ref()
+                    .is_none_or(|current| !current.default)
+            {
+                builder.position = Some(position);
+            }
+        }
+    }
+
+    fn visit_text(
+        &mut self,
+        paths: &Paths,
+        current_path: &[StrRef],
+        text: CollectedText<'_, 'a>,
+    ) -> Result<(), ParseErrorKind> {
+        if let Some(date_time) = self.lifecycle_date_time(paths, current_path) {
+            // A nil date may still have whitespace between its tags
+            let text = text.into_str();
+            if !text.trim().is_empty() {
+                *date_time = Some(parse_date_time(&text)?);
+            }
+            return Ok(());
+        }
+
+        if let Some((names_path, names)) = self.names(paths)
+            && path_starts_with(current_path, names_path)
+        {
+            let relative_path = &current_path[names_path.len()..];
+            let Some(name) = names.last_mut() else {
+                return Ok(());
+            };
+            if relative_path == paths.name_language {
+                name.language = Some(text.into_str());
+            } else if let Some(spelling) = name.spellings.last_mut() {
+                if relative_path == paths.name_spelling_text {
+                    spelling.text = text.into_str();
+                } else if relative_path == paths.name_spelling_script {
+                    spelling.script = Some(text.into_str());
+                }
+            }
+            return Ok(());
+        }
+
+        // This is where you'll add specific checks for different XML paths
+        match &mut self.feature_member {
+            FeatureMemberBuilder::Address(builder) => {
+                if current_path == paths.address_position_pos {
+                    let text = text.into_str();
+                    let mut coordinates = text.split_ascii_whitespace().map(str::parse::<f64>);
+                    let (Some(Ok(x)), Some(Ok(y))) = (coordinates.next(), coordinates.next())
+                    else {
+                        return Err(ParseErrorKind::InvalidPosition(text.into_owned()));
+                    };
+                    if let Some(position_builder) = builder.position_builder.as_mut() {
+                        position_builder.pos = Some((x, y));
+                    }
+                } else if current_path == paths.address_position_default {
+                    if let Some(position_builder) = builder.position_builder.as_mut() {
+                        // An xs:boolean, which may also be written as a digit
+                        position_builder.default = matches!(text.into_str().trim(), "true" | "1");
+                    }
+                } else if path_starts_with(current_path, &paths.address_locator)
+                    && current_path[paths.address_locator.len()..] == paths.locator_designator_text
+                    && let Some(locator) = builder.locators.last_mut()
+                    && let Some(designator) = locator.designators.last_mut()
+                {
+                    designator.designator = Some(text.into_str());
+                }
+            }
+            FeatureMemberBuilder::AdminUnitName(_)
+            | FeatureMemberBuilder::AddressAreaName(_)
+            | FeatureMemberBuilder::ThoroughfareName(_) => {
+                // Names are handled above
+            }
+            FeatureMemberBuilder::PostalDescriptor(builder) => {
+                if current_path == paths.postal_descriptor_post_code {
+                    builder.post_code = Some(text.into_str());
+                } else if current_path == paths.postal_descriptor_post_name
+                    && builder.post_name.is_none()
+                {
+                    builder.post_name = Some(text.into_str());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the path of the `gn:GeographicalName`s of the feature, and the names collected so
+    /// far, if the feature has names.
+    fn names<'p>(
+        &mut self,
+        paths: &'p Paths,
+    ) -> Option<(&'p XmlPath, &mut Vec<GeographicalName<'a>>)> {
+        match &mut self.feature_member {
+            FeatureMemberBuilder::AdminUnitName(builder) => {
+                Some((&paths.admin_unit_names, &mut builder.names))
+            }
+            FeatureMemberBuilder::AddressAreaName(builder) => {
+                Some((&paths.address_area_names, &mut builder.names))
+            }
+            FeatureMemberBuilder::ThoroughfareName(builder) => {
+                Some((&paths.thoroughfare_names, &mut builder.names))
+            }
+            FeatureMemberBuilder::Address(builder) => {
+                let name = builder.locators.last_mut()?.names.last_mut()?;
+                Some((&paths.address_locator_names, &mut name.names))
+            }
+            FeatureMemberBuilder::PostalDescriptor(_) => None,
+        }
+    }
+
+    /// Returns the life-cycle date the current element holds, if it is one.
+    fn lifecycle_date_time(
+        &mut self,
+        paths: &Paths,
+        current_path: &[StrRef],
+    ) -> Option<&mut Option<NaiveDateTime>> {
+        let name = *current_path.last()?;
+        if !paths.is_feature_property(current_path, name) {
+            None
+        } else if name == paths.valid_from {
+            Some(&mut self.lifecycle.valid_from)
+        } else if name == paths.valid_to {
+            Some(&mut self.lifecycle.valid_to)
+        } else if name == paths.begin_lifespan_version {
+            Some(&mut self.lifecycle.begin_lifespan_version)
+        } else if name == paths.end_lifespan_version {
+            Some(&mut self.lifecycle.end_lifespan_version)
+        } else {
+            None
+        }
+    }
+
+    fn finish(self, resolver: &ReferenceResolver) -> Result<FeatureMember<'a>, ParseErrorKind> {
+        let local_id = self.local_id.ok_or(ParseErrorKind::MissingLocalId)?;
+        let lifecycle = self.lifecycle;
+        let identifier = self.identifier;
+        let resolve = |href: Option<String>| href.map(|href| resolver.resolve(href).1);
+        Ok(match self.feature_member {
+            FeatureMemberBuilder::Address(builder) => {
+                let mut admin_unit_ref = None;
+                let mut address_area_ref = None;
+                let mut thoroughfare_ref = None;
+                let mut postal_descriptor_ref = None;
+                let mut other_component_refs = Vec::new();
+                let number = first_designator(&builder.locators, "addressNumber");
+                let number_extension =
+                    first_designator(&builder.locators, "addressNumberExtension");
+                let number_2nd_extension =
+                    first_designator(&builder.locators, "addressNumber2ndExtension");
+                let postal_delivery_identifier =
+                    first_designator(&builder.locators, "postalDeliveryIdentifier");
+                for href in builder.components {
+                    let (feature_type, reference) = resolver.resolve(href);
+                    let field = match feature_type {
+                        Some(FeatureMemberType::AdminUnitName) => &mut admin_unit_ref,
+                        Some(FeatureMemberType::AddressAreaName) => &mut address_area_ref,
+                        Some(FeatureMemberType::ThoroughfareName) => &mut thoroughfare_ref,
+                        Some(FeatureMemberType::PostalDescriptor) => &mut postal_descriptor_ref,
+                        Some(FeatureMemberType::Address) | None => {
+                            other_component_refs.push(reference);
+                            continue;
+                        }
+                    };
+                    *field = Some(reference);
+                }
+                FeatureMember::Address {
+                    local_id,
+                    number,
+                    number_extension,
+                    number_2nd_extension,
+                    postal_delivery_identifier,
+                    admin_unit_ref,
+                    address_area_ref,
+                    thoroughfare_ref,
+                    postal_descriptor_ref,
+                    other_component_refs,
+                    parent_address_ref: resolve(builder.parent_address),
+                    building_refs: builder
+                        .buildings
+                        .into_iter()
+                        .map(|href| resolver.resolve(href).1)
+                        .collect(),
+                    locators: builder.locators,
+                    position: builder.position,
+                    lifecycle,
+                    identifier,
+                }
+            }
+            FeatureMemberBuilder::AdminUnitName(builder) => FeatureMember::AdminUnitName {
+                local_id,
+                name: first_spelling(&builder.names),
+                names: builder.names,
+                lifecycle,
+                identifier,
+            },
+            FeatureMemberBuilder::AddressAreaName(builder) => FeatureMember::AddressAreaName {
+                local_id,
+                name: first_spelling(&builder.names),
+                names: builder.names,
+                situated_in_ref: resolve(builder.situated_in_ref),
+                lifecycle,
+                identifier,
+            },
+            FeatureMemberBuilder::ThoroughfareName(builder) => FeatureMember::ThoroughfareName {
+                local_id,
+                name: first_spelling(&builder.names),
+                names: builder.names,
+                situated_in_ref: resolve(builder.situated_in_ref),
+                lifecycle,
+                identifier,
+            },
+            FeatureMemberBuilder::PostalDescriptor(builder) => FeatureMember::PostalDescriptor {
+                local_id,
+                post_code: builder.post_code,
+                post_name: builder.post_name,
+                admin_unit_ref: resolve(builder.admin_unit_ref),
+                lifecycle,
+                identifier,
+            },
+        })
+    }
+}
+
+fn first_spelling<'a>(names: &[GeographicalName<'a>]) -> Option<Cow<'a, str>> {
+    let spelling = names.first()?.spellings.first()?;
+    Some(spelling.text.clone())
+}
+
+/// Returns the first designator with the given `LocatorDesignatorTypeValue` code.
+fn first_designator<'a>(locators: &[Locator<'a>], designator_type: &str) -> Option<Cow<'a, str>> {
+    locators
+        .iter()
+        .flat_map(|locator| &locator.designators)
+        .find(|designator| designator.designator_type.as_deref() == Some(designator_type))
+        .and_then(|designator| designator.designator.clone())
+}
+
+/// Returns the value of the attribute with the given namespace and local name.
+fn attribute<R>(
+    reader: &NsReader<R>,
+    e: &quick_xml::events::BytesStart<'_>,
+    namespace: &str,
+    local_name: &str,
+) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|attr| {
+            let (attr_namespace, attr_local_name) = reader.resolve_attribute(attr.key);
+            matches!(attr_namespace, ResolveResult::Bound(attr_namespace) if attr_namespace.as_ref() == namespace.as_bytes())
+                && attr_local_name.as_ref() == local_name.as_bytes()
+        })
+        .map(|attr| String::from_utf8_lossy(&attr.value).into_owned())
+}
+
+fn xlink_href<R>(reader: &NsReader<R>, e: &quick_xml::events::BytesStart<'_>) -> Option<String> {
+    attribute(reader, e, XLINK_NAMESPACE, "href")
+}
+
+/// Returns the value of an INSPIRE code list URI, e.g. `entrance` for
+/// `http://inspire.ec.europa.eu/codelist/GeometrySpecificationValue/entrance`.
+fn codelist_value(href: &str) -> String {
+    href.rsplit('/').next().unwrap_or(href).to_string()
+}
+
+/// Parses an `xs:dateTime`, converting it to UTC if it has a time zone. A plain `xs:date` is
+/// accepted as midnight of that day.
+pub(crate) fn parse_date_time(text: &str) -> Result<NaiveDateTime, ParseErrorKind> {
+    let text = text.trim();
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(text) {
+        return Ok(date_time.naive_utc());
+    }
+    if let Ok(date_time) = NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f") {
+        return Ok(date_time);
+    }
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .map(|date| date.and_time(Default::default()))
+        .map_err(|_| ParseErrorKind::InvalidDateTime(text.to_string()))
+}
+
+/// The text of an element, which is only copied if the builders need it.
+#[derive(Clone, Copy)]
+enum CollectedText<'t, 'a> {
+    /// Text borrowed from the input, which is kept borrowed.
+    Borrowed(&'a str),
+    /// Text in the buffer of the collector, which is copied.
+    Buffered(&'t str),
+}
+
+impl<'a> CollectedText<'_, 'a> {
+    fn into_str(self) -> Cow<'a, str> {
+        match self {
+            CollectedText::Borrowed(text) => Cow::Borrowed(text),
+            CollectedText::Buffered(text) => Cow::Owned(text.to_string()),
+        }
+    }
+}
+
+fn name_to_str(name: &[u8]) -> Result<&str, ParseErrorKind> {
+    str::from_utf8(name).map_err(ParseErrorKind::InvalidUtf8)
+}
+
+/// Why the collector wants the iterator to read past an element without visiting its contents.
+enum Skip {
+    /// A feature member of an unknown type, in lenient mode, with its tag.
+    Unknown(String),
+    /// A feature member or property that is not selected.
+    Unselected,
+    /// A child of a collection that neither is nor holds feature members, such as its
+    /// `gml:boundedBy`.
+    Unrelated,
+}
+
+struct AddressCollector<'a> {
+    string_interner: StringInterner,
+    paths: Paths,
+    current_path: XmlPath,
+    current_member: Option<CurrentMemberBuilder<'a>>,
+    /// Where the element of the current feature member is in `current_path`.
+    member_start: usize,
+    resolver: ReferenceResolver,
+    mode: Mode,
+    selection: Selection,
+    /// The properties, relative to the feature element, that the selection leaves out.
+    skipped_properties: Vec<XmlPath>,
+    /// The number of feature members the selection has left out.
+    unselected: u64,
+    /// Buffer for building the Clark notation of element names.
+    name_buf: String,
+    /// Byte offset of the event being visited, used when reporting errors.
+    position: u64,
+    /// The line the event being visited starts on.
+    line: u64,
+    /// The text of the current element so far. quick-xml reports the text around CDATA sections
+    /// and comments as separate events, so text is collected until the next tag.
+    text: String,
+    /// The text of the current element instead, while it is a single piece borrowed from the
+    /// input.
+    borrowed_text: Option<&'a str>,
+    /// Byte offset of the first piece of the text.
+    text_position: u64,
+    text_line: u64,
+}
+
+impl<'a> AddressCollector<'a> {
+    fn new() -> Self {
+        let mut string_interner = StringInterner::default();
+        let paths = Paths::new(&mut string_interner);
+        AddressCollector {
+            string_interner,
+            paths,
+            current_path: Vec::new(),
+            current_member: None,
+            member_start: 0,
+            resolver: ReferenceResolver::default(),
+            mode: Mode::default(),
+            selection: Selection::all(),
+            skipped_properties: Vec::new(),
+            unselected: 0,
+            name_buf: String::new(),
+            position: 0,
+            line: 1,
+            text: String::new(),
+            borrowed_text: None,
+            text_position: 0,
+            text_line: 1,
+        }
+    }
+
+    /// Only collects the feature members and properties in `selection`.
+    fn select(&mut self, selection: Selection) {
+        self.skipped_properties = [Field::Position, Field::Locators, Field::Names]
+            .into_iter()
+            .filter(|field| !selection.includes_field(*field))
+            .flat_map(|field| self.paths.field_properties(field))
+            .collect();
+        self.selection = selection;
+    }
+
+    fn error(&self, kind: ParseErrorKind) -> Error {
+        self.error_at(self.position, self.line, kind)
+    }
+
+    fn error_at(&self, position: u64, line: u64, kind: ParseErrorKind) -> Error {
+        let path = self
+            .current_path
+            .iter()
+            .map(|name_ref| self.string_interner.get(*name_ref))
+            .collect::<Vec<_>>()
+            .join("/");
+        Error::Parse {
+            position,
+            line,
+            path,
+            kind,
+        }
+    }
+
+    /// Interns the namespace-resolved name of an element.
+    fn intern_name<R>(&mut self, reader: &NsReader<R>, name: QName) -> Result<StrRef> {
+        let (namespace, local_name) = reader.resolve_element(name);
+        let local_name = name_to_str(local_name.into_inner()).map_err(|kind| self.error(kind))?;
+        self.name_buf.clear();
+        match namespace {
+            ResolveResult::Bound(namespace) => {
+                let namespace =
+                    name_to_str(namespace.into_inner()).map_err(|kind| self.error(kind))?;
+                self.name_buf.push('{');
+                self.name_buf.push_str(namespace);
+                self.name_buf.push('}');
+            }
+            ResolveResult::Unbound => {}
+            ResolveResult::Unknown(prefix) => {
+                let prefix = String::from_utf8_lossy(&prefix).into_owned();
+                return Err(self.error(ParseErrorKind::UnknownPrefix(prefix)));
+            }
+        }
+        self.name_buf.push_str(local_name);
+        Ok(self.string_interner.intern(&self.name_buf))
+    }
+
+    /// Returns whether the current element, which is not part of a feature member, is a property of
+    /// a collection other than its members, such as `gml:boundedBy` or `gml:description`.
+    fn is_collection_property(&self) -> bool {
+        let [.., parent, name_ref] = self.current_path[..] else {
+            return false;
+        };
+        self.paths.containers.contains(&parent)
+            && !self.paths.member_properties.contains(&parent)
+            && !self.paths.containers.contains(&name_ref)
+    }
+
+    /// Returns whether the current element, which is not part of a feature member, starts one.
+    ///
+    /// Feature members are the children of `gml:featureMember`, `gml:featureMembers` and
+    /// `wfs:member`, and collections can be nested in those and in `wfs:additionalObjects`, as
+    /// WFS 2.0 responses do. The root element can be anything.
+    fn is_member_start(&self) -> Result<bool> {
+        let name_ref = *self.current_path.last().expect("an element is open");
+        if self.current_path.len() == 1 || self.paths.containers.contains(&name_ref) {
+            return Ok(false);
+        }
+        let parent = self.current_path[self.current_path.len() - 2];
+        if self.paths.member_properties.contains(&parent) {
+            return Ok(true);
+        }
+        let tag = self.string_interner.get(name_ref).to_string();
+        Err(self.error(ParseErrorKind::UnexpectedElement(tag)))
+    }
+
+    /// Returns whether the element has to be skipped, instead of its contents being visited.
+    fn visit_start<R>(
+        &mut self,
+        reader: &NsReader<R>,
+        e: quick_xml::events::BytesStart,
+    ) -> Result<Option<Skip>> {
+        self.finish_text()?;
+        let name_ref = self.intern_name(reader, e.name())?;
+        self.current_path.push(name_ref);
+
+        if let Some(current_member) = self.current_member.as_mut() {
+            let relative_path = &self.current_path[self.member_start..];
+            if relative_path.len() == 2
+                && self
+                    .skipped_properties
+                    .iter()
+                    .any(|property| property == relative_path)
+            {
+                self.current_path.pop();
+                return Ok(Some(Skip::Unselected));
+            }
+            current_member
+                .visit_start(
+                    &self.paths,
+                    &self.current_path[self.member_start..],
+                    reader,
+                    e,
+                )
+                .map_err(|kind| self.error(kind))?;
+        } else if self.is_collection_property() {
+            self.current_path.pop();
+            return Ok(Some(Skip::Unrelated));
+        } else if self.is_member_start()? {
+            // A new member is starting
+            let tag = self.string_interner.get(name_ref);
+            let feature_type = match FeatureMemberType::from_tag(tag) {
+                Ok(feature_type) => feature_type,
+                Err(ParseErrorKind::UnknownFeatureMember(tag)) if self.mode == Mode::Lenient => {
+                    self.current_path.pop();
+                    return Ok(Some(Skip::Unknown(tag)));
+                }
+                Err(kind) => return Err(self.error(kind)),
+            };
+            if !self.selection.includes(feature_type) {
+                self.current_path.pop();
+                self.unselected += 1;
+                return Ok(Some(Skip::Unselected));
+            }
+            let feature_member = FeatureMemberBuilder::new(feature_type);
+            self.current_member = Some(CurrentMemberBuilder {
+                local_id: None,
+                lifecycle: Lifecycle::default(),
+                identifier: Identifier {
+                    gml_id: attribute(reader, &e, GML_NAMESPACE, "id"),
+                    ..Identifier::default()
+                },
+                feature_member,
+            });
+            self.member_start = self.current_path.len() - 1;
+        }
+        Ok(None)
+    }
+
+    /// Returns the feature member that the end tag completes, if any.
+    fn visit_end<R>(
+        &mut self,
+        reader: &NsReader<R>,
+        e: quick_xml::events::BytesEnd,
+    ) -> Result<Option<FeatureMember<'a>>> {
+        self.finish_text()?;
+        let name_ref = self.intern_name(reader, e.name())?;
+        if self.current_path.last() != Some(&name_ref) {
+            let expected = self
+                .current_path
+                .last()
+                .map(|expected| self.string_interner.get(*expected).to_string());
+            return Err(self.error(ParseErrorKind::MismatchedEndTag {
+                expected,
+                found: self.string_interner.get(name_ref).to_string(),
+            }));
+        }
+
+        if self.current_path.len() > self.member_start + 1
+            && let Some(current_member) = self.current_member.as_mut()
+        {
+            current_member.visit_end(&self.paths, &self.current_path[self.member_start..], e);
+        }
+        self.current_path.pop();
+
+        if self.current_member.is_some() && self.current_path.len() == self.member_start {
+            let current_member = self.current_member.take().unwrap();
+            let feature_member = current_member
+                .finish(&self.resolver)
+                .map_err(|kind| self.error(kind))?;
+            self.resolver.register(&feature_member);
+            Ok(Some(feature_member))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Adds a piece of text, which is copied.
+    fn visit_text(&mut self, text: &str) {
+        // Text outside of a feature member is only whitespace between elements
+        if self.current_member.is_none() {
+            return;
+        }
+        if self.text.is_empty() {
+            self.text_position = self.position;
+            self.text_line = self.line;
+        }
+        if let Some(borrowed_text) = self.borrowed_text.take() {
+            self.text.push_str(borrowed_text);
+        }
+        self.text.push_str(text);
+    }
+
+    /// Adds a piece of text, which is kept borrowed if it is the only one.
+    fn visit_borrowed_text(&mut self, text: Cow<'a, str>) {
+        match text {
+            Cow::Borrowed(text)
+                if self.current_member.is_some()
+                    && self.text.is_empty()
+                    && self.borrowed_text.is_none() =>
+            {
+                self.text_position = self.position;
+                self.text_line = self.line;
+                self.borrowed_text = Some(text);
+            }
+            text => self.visit_text(&text),
+        }
+    }
+
+    /// Passes the text collected since the last tag to the builders.
+    fn finish_text(&mut self) -> Result<()> {
+        let text = match self.borrowed_text.take() {
+            Some(text) => CollectedText::Borrowed(text),
+            None if !self.text.is_empty() => CollectedText::Buffered(&self.text),
+            None => return Ok(()),
+        };
+        let Some(current_member) = self.current_member.as_mut() else {
+            self.text.clear();
+            return Ok(());
+        };
+
+        let result = if path_ends_with(&self.current_path, &self.paths.local_id_suffix) {
+            current_member.local_id = Some(text.into_str());
+            Ok(())
+        } else if path_ends_with(&self.current_path, &self.paths.version_id_suffix) {
+            current_member.identifier.version_id = Some(text.into_str());
+            Ok(())
+        } else if path_ends_with(&self.current_path, &self.paths.namespace_suffix) {
+            current_member.identifier.namespace = Some(text.into_str());
+            Ok(())
+        } else {
+            current_member.visit_text(&self.paths, &self.current_path[self.member_start..], text)
+        };
+        self.text.clear();
+        result.map_err(|kind| self.error_at(self.text_position, self.text_line, kind))
+    }
+}
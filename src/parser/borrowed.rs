@@ -0,0 +1,121 @@
+//! Parsing a document that is in memory as a whole, such as a memory-mapped file, without copying
+//! the text of its feature members.
+
+use quick_xml::{events::Event, reader::NsReader};
+
+use super::{
+    AddressCollector, FeatureMembers, Mode, Parser, Selection,
+    events::{EventSource, line_breaks},
+};
+use crate::{
+    emitter::FeatureMemberEmitter,
+    error::{Error, Result},
+};
+
+impl<'a> Parser<&'a [u8]> {
+    /// Reads the whole document like [`Parser::parse`], but passes the emitter feature members
+    /// that borrow their text from the document.
+    pub fn parse_borrowed<T>(self, emitter: T) -> Result<T>
+    where
+        T: FeatureMemberEmitter,
+    {
+        self.into_borrowed_iter().emit_all(emitter)
+    }
+
+    /// Returns an iterator over feature members that borrow their text from the document.
+    pub fn into_borrowed_iter(self) -> BorrowedFeatureMemberIter<'a> {
+        FeatureMembers::with_source(
+            SliceEvents::new(self.xml_reader),
+            self.mode,
+            self.selection,
+            self.rejects,
+        )
+    }
+}
+
+/// Pulls feature members out of a document in memory one at a time, like
+/// [`FeatureMemberIter`](super::FeatureMemberIter), but without copying their text.
+///
+/// Text is only copied when the document escapes characters in it, or splits it up with CDATA
+/// sections or comments.
+pub type BorrowedFeatureMemberIter<'a> = FeatureMembers<'a, SliceEvents<'a>>;
+
+impl<'a> BorrowedFeatureMemberIter<'a> {
+    pub fn new(xml_reader: NsReader<&'a [u8]>) -> Self {
+        FeatureMembers::with_source(
+            SliceEvents::new(xml_reader),
+            Mode::default(),
+            Selection::all(),
+            None,
+        )
+    }
+}
+
+/// Reads events from a document in memory, so their text can be borrowed from it.
+pub struct SliceEvents<'a> {
+    xml_reader: NsReader<&'a [u8]>,
+    /// The line the next event starts on.
+    line: u64,
+}
+
+impl<'a> SliceEvents<'a> {
+    fn new(xml_reader: NsReader<&'a [u8]>) -> Self {
+        SliceEvents {
+            xml_reader,
+            line: 1,
+        }
+    }
+
+    /// Reads an event, counting the lines it spans.
+    fn next_event(&mut self) -> Result<Event<'a>> {
+        let event = self.xml_reader.read_event().map_err(|source| Error::Xml {
+            position: self.xml_reader.error_position(),
+            line: self.line,
+            source,
+        })?;
+        self.line += line_breaks(&event);
+        Ok(event)
+    }
+}
+
+#[allow(private_interfaces)]
+impl<'a> EventSource<'a> for SliceEvents<'a> {
+    type Reader = &'a [u8];
+
+    fn xml_reader(&self) -> &NsReader<&'a [u8]> {
+        &self.xml_reader
+    }
+
+    fn read_event(
+        &mut self,
+        collector: &mut AddressCollector<'a>,
+    ) -> Result<(Event<'_>, &NsReader<&'a [u8]>)> {
+        collector.position = self.xml_reader.buffer_position();
+        collector.line = self.line;
+        let event = self.next_event()?;
+        match &event {
+            Event::Text(e) => {
+                let text = e.unescape().map_err(|source| Error::Xml {
+                    position: collector.position,
+                    line: collector.line,
+                    source,
+                })?;
+                collector.visit_borrowed_text(text);
+            }
+            Event::CData(e) => {
+                let text = e.decode().map_err(|source| Error::Xml {
+                    position: collector.position,
+                    line: collector.line,
+                    source: source.into(),
+                })?;
+                collector.visit_borrowed_text(text);
+            }
+            _ => {}
+        }
+        Ok((event, &self.xml_reader))
+    }
+
+    fn skip_event(&mut self) -> Result<Event<'a>> {
+        self.next_event()
+    }
+}
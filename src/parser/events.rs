@@ -0,0 +1,120 @@
+//! Where feature member iterators read their events from: a buffered reader, or a document in
+//! memory.
+
+use std::io::BufRead;
+
+use quick_xml::{events::Event, reader::NsReader};
+
+use super::AddressCollector;
+use crate::error::{Error, Result};
+
+/// Reads the events of a document for a [`FeatureMembers`](super::FeatureMembers) iterator.
+///
+/// The trait is public only because it bounds the iterator; it can't be named outside the crate.
+#[allow(private_interfaces)]
+pub trait EventSource<'a> {
+    type Reader;
+
+    fn xml_reader(&self) -> &NsReader<Self::Reader>;
+
+    /// Reads the next event, with the reader to resolve its names. Text is passed to `collector`
+    /// here, as only the source knows whether it outlives the event.
+    fn read_event(
+        &mut self,
+        collector: &mut AddressCollector<'a>,
+    ) -> Result<(Event<'_>, &NsReader<Self::Reader>)>;
+
+    /// Reads the next event of an element that is skipped, without visiting its text.
+    fn skip_event(&mut self) -> Result<Event<'_>>;
+}
+
+/// Reads events from a buffered reader into a buffer, so their text is copied.
+pub struct ReaderEvents<R> {
+    xml_reader: NsReader<R>,
+    buf: Vec<u8>,
+    /// The line the next event starts on.
+    line: u64,
+}
+
+impl<R> ReaderEvents<R> {
+    pub(super) fn new(xml_reader: NsReader<R>) -> Self {
+        ReaderEvents {
+            xml_reader,
+            buf: Vec::new(),
+            line: 1,
+        }
+    }
+}
+
+#[allow(private_interfaces)]
+impl<R> EventSource<'static> for ReaderEvents<R>
+where
+    R: BufRead,
+{
+    type Reader = R;
+
+    fn xml_reader(&self) -> &NsReader<R> {
+        &self.xml_reader
+    }
+
+    fn read_event(
+        &mut self,
+        collector: &mut AddressCollector<'static>,
+    ) -> Result<(Event<'_>, &NsReader<R>)> {
+        collector.position = self.xml_reader.buffer_position();
+        collector.line = self.line;
+        let event = read_event_into(&mut self.xml_reader, &mut self.buf, &mut self.line)?;
+        match &event {
+            Event::Text(e) => {
+                let text = e.unescape().map_err(|source| Error::Xml {
+                    position: collector.position,
+                    line: collector.line,
+                    source,
+                })?;
+                collector.visit_text(&text);
+            }
+            Event::CData(e) => {
+                let text = e.decode().map_err(|source| Error::Xml {
+                    position: collector.position,
+                    line: collector.line,
+                    source: source.into(),
+                })?;
+                collector.visit_text(&text);
+            }
+            _ => {}
+        }
+        Ok((event, &self.xml_reader))
+    }
+
+    fn skip_event(&mut self) -> Result<Event<'_>> {
+        read_event_into(&mut self.xml_reader, &mut self.buf, &mut self.line)
+    }
+}
+
+/// Reads an event into `buf`, counting the lines it spans.
+fn read_event_into<'b, R>(
+    xml_reader: &mut NsReader<R>,
+    buf: &'b mut Vec<u8>,
+    line: &mut u64,
+) -> Result<Event<'b>>
+where
+    R: BufRead,
+{
+    buf.clear();
+    let event = xml_reader
+        .read_event_into(buf)
+        .map_err(|source| Error::Xml {
+            position: xml_reader.error_position(),
+            line: *line,
+            source,
+        })?;
+    *line += line_breaks(&event);
+    Ok(event)
+}
+
+/// Counts the line breaks in `data`, such as the content of an event. That is where all line
+/// breaks of an event are, as the delimiters of markup, such as `<`, `/>` and `<![CDATA[`, have
+/// none.
+pub(crate) fn line_breaks(data: &[u8]) -> u64 {
+    data.iter().filter(|&&b| b == b'\n').count() as u64
+}
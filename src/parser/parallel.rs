@@ -0,0 +1,646 @@
+//! Parsing a single document on several threads.
+//!
+//! The document is split into chunks of whole feature members by reading its tags, without
+//! resolving namespaces or decoding text. Every chunk is made a document of its own by putting the
+//! part of the original document before the first feature member in front of it, and closing the
+//! elements that part left open after it. Chunks are parsed on worker threads and their feature
+//! members are handed back in the order of the document.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, Read, Write};
+use std::num::NonZeroUsize;
+use std::rc::Rc;
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{Arc, Mutex, mpsc};
+
+use quick_xml::{Reader, events::Event};
+
+use super::{Mode, Parser, Selection, events::line_breaks, reference::ReferenceResolver};
+use crate::{
+    FeatureMember,
+    emitter::FeatureMemberEmitter,
+    error::{Error, Result},
+};
+
+const DEFAULT_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// The local names of the elements whose children are feature members.
+const MEMBER_PROPERTIES: [&[u8]; 3] = [b"featureMember", b"featureMembers", b"member"];
+
+/// The local names of collections, which can be nested in a member property.
+const COLLECTIONS: [&[u8]; 2] = [b"FeatureCollection", b"SimpleFeatureCollection"];
+
+/// Parses an INSPIRE addresses GML document like [`Parser`], but on several threads.
+///
+/// Only the parsing happens on the worker threads: the input is read, and feature members are
+/// emitted, on the calling thread, so neither has to be [`Send`]. References to features are
+/// resolved exactly as [`Parser`] resolves them.
+///
+/// The document is only split before feature members in the same kind of container as the first
+/// one, e.g. `gml:featureMember` or the inner `wfs:member` of nested collections, so feature
+/// members in `wfs:additionalObjects` end up in the last chunk.
+pub struct ParallelParser<R> {
+    chunks: ChunkReader<R>,
+    mode: Mode,
+    selection: Selection,
+    rejects: Option<Box<dyn Write>>,
+    threads: usize,
+    skipped: BTreeMap<String, u64>,
+}
+
+impl<R> ParallelParser<R>
+where
+    R: BufRead,
+{
+    /// Creates a parser that uses as many threads as there are CPU cores.
+    pub fn new(reader: R) -> Self {
+        ParallelParser {
+            chunks: ChunkReader::new(reader, DEFAULT_CHUNK_SIZE),
+            mode: Mode::default(),
+            selection: Selection::all(),
+            rejects: None,
+            threads: std::thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            skipped: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// See [`Parser::with_selection`].
+    pub fn with_selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// See [`Parser::with_rejects`]. Skipped feature members are written in document order.
+    pub fn with_rejects(mut self, rejects: impl Write + 'static) -> Self {
+        self.rejects = Some(Box::new(rejects));
+        self
+    }
+
+    /// Sets the number of threads that parse chunks.
+    pub fn with_threads(mut self, threads: NonZeroUsize) -> Self {
+        self.threads = threads.get();
+        self
+    }
+
+    /// Sets the size in bytes above which a chunk is ended at the next feature member.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunks.chunk_size = chunk_size;
+        self
+    }
+
+    /// Reads the whole document, emitting its feature members in order, like [`Parser::parse`].
+    pub fn parse<T>(mut self, mut emitter: T) -> Result<T>
+    where
+        T: FeatureMemberEmitter,
+    {
+        emitter.start()?;
+        self.for_each(|feature_member| emitter.emit(feature_member))?;
+        emitter.end()?;
+        Ok(emitter)
+    }
+
+    /// Calls `f` with every feature member of the document, in order.
+    ///
+    /// Feature members before the first error in the document are still passed to `f`.
+    pub fn for_each<F>(&mut self, mut f: F) -> Result<()>
+    where
+        F: FnMut(FeatureMember<'static>) -> Result<()>,
+    {
+        let (chunk_sender, chunk_receiver) = mpsc::channel::<Chunk>();
+        let (result_sender, result_receiver) = mpsc::channel();
+        let chunk_receiver = Arc::new(Mutex::new(chunk_receiver));
+        let mode = self.mode;
+        let selection = &self.selection.clone();
+        let keep_rejects = self.rejects.is_some();
+
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..self.threads)
+                .map(|_| {
+                    let chunk_receiver = chunk_receiver.clone();
+                    let result_sender = result_sender.clone();
+                    scope.spawn(move || {
+                        parse_chunks(
+                            &chunk_receiver,
+                            &result_sender,
+                            mode,
+                            selection,
+                            keep_rejects,
+                        )
+                    })
+                })
+                .collect();
+            drop(result_sender);
+            // Returning drops the chunk sender, which stops the workers
+            let result = self.run(chunk_sender, result_receiver, &mut f);
+            // Joining the workers here keeps a panic in one of them from panicking the scope
+            let mut panicked = false;
+            for worker in workers {
+                panicked |= worker.join().is_err();
+            }
+            if panicked {
+                return Err(Error::ThreadPanicked);
+            }
+            result
+        })
+    }
+
+    /// Returns how many feature members have been skipped in lenient mode, per tag in Clark
+    /// notation.
+    pub fn skipped(&self) -> &BTreeMap<String, u64> {
+        &self.skipped
+    }
+
+    /// Reads chunks and hands them to the workers, keeping a few chunks per worker in flight,
+    /// and passes the results to `f` in order.
+    fn run<F>(
+        &mut self,
+        chunk_sender: Sender<Chunk>,
+        result_receiver: Receiver<ChunkResult>,
+        f: &mut F,
+    ) -> Result<()>
+    where
+        F: FnMut(FeatureMember<'static>) -> Result<()>,
+    {
+        let max_in_flight = self.threads * 2;
+        let mut resolver = ReferenceResolver::default();
+        let mut results = HashMap::new();
+        let mut read = 0;
+        let mut emitted = 0;
+        let mut eof = false;
+        let mut read_error = None;
+
+        loop {
+            if !eof && read - emitted < max_in_flight {
+                match self.chunks.next_chunk(read) {
+                    Ok(Some(chunk)) => {
+                        chunk_sender
+                            .send(chunk)
+                            .map_err(|_| Error::ThreadPanicked)?;
+                        read += 1;
+                    }
+                    Ok(None) => eof = true,
+                    // Reported once the chunks before it have been emitted
+                    Err(error) => {
+                        eof = true;
+                        read_error = Some(error);
+                    }
+                }
+                continue;
+            }
+            if emitted == read {
+                return read_error.map_or(Ok(()), Err);
+            }
+
+            let result = result_receiver.recv().map_err(|_| Error::ThreadPanicked)?;
+            results.insert(result.index, result);
+            while let Some(result) = results.remove(&emitted) {
+                self.emit_chunk(result, &mut resolver, f)?;
+                emitted += 1;
+            }
+        }
+    }
+
+    /// Passes the feature members of a chunk to `f`, first resolving the references to features
+    /// in earlier chunks, which the worker did not know about.
+    fn emit_chunk<F>(
+        &mut self,
+        result: ChunkResult,
+        resolver: &mut ReferenceResolver,
+        f: &mut F,
+    ) -> Result<()>
+    where
+        F: FnMut(FeatureMember<'static>) -> Result<()>,
+    {
+        if let Some(rejects) = self.rejects.as_mut() {
+            rejects.write_all(&result.rejects)?;
+            rejects.flush()?;
+        }
+        for (tag, count) in result.skipped {
+            *self.skipped.entry(tag).or_default() += count;
+        }
+
+        for (mut feature_member, unresolved) in result.feature_members {
+            for href in unresolved {
+                resolver.patch(&mut feature_member, &href);
+            }
+            f(feature_member)?;
+        }
+        if let Some(error) = result.error {
+            return Err(error);
+        }
+        resolver.extend(result.resolver);
+        Ok(())
+    }
+}
+
+/// A part of the document that is a document of its own.
+struct Chunk {
+    index: usize,
+    data: Vec<u8>,
+    /// The length of the part of the original document that is put in front of every chunk.
+    header_len: usize,
+    /// Where the chunk's own bytes start in the original document.
+    offset: u64,
+    /// The number of line breaks before `offset` in the original document.
+    line_breaks: u64,
+}
+
+impl Chunk {
+    /// Makes the byte positions and lines in an error relative to the original document.
+    fn relocate(&self, error: Error) -> Error {
+        let relocate = |position: u64, line: u64| {
+            let header_len = self.header_len as u64;
+            if position < header_len {
+                (position, line)
+            } else {
+                let header_line_breaks = line_breaks(&self.data[..self.header_len]);
+                (
+                    position - header_len + self.offset,
+                    line - header_line_breaks + self.line_breaks,
+                )
+            }
+        };
+        match error {
+            Error::Xml {
+                position,
+                line,
+                source,
+            } => {
+                let (position, line) = relocate(position, line);
+                Error::Xml {
+                    position,
+                    line,
+                    source,
+                }
+            }
+            Error::Parse {
+                position,
+                line,
+                path,
+                kind,
+            } => {
+                let (position, line) = relocate(position, line);
+                Error::Parse {
+                    position,
+                    line,
+                    path,
+                    kind,
+                }
+            }
+            error => error,
+        }
+    }
+}
+
+struct ChunkResult {
+    index: usize,
+    /// The feature members, each with the `#gml-id` references the worker could not resolve.
+    feature_members: Vec<(FeatureMember<'static>, Vec<String>)>,
+    /// The error that ended the chunk early, if any.
+    error: Option<Error>,
+    /// The features the chunk registered.
+    resolver: ReferenceResolver,
+    skipped: BTreeMap<String, u64>,
+    rejects: Vec<u8>,
+}
+
+/// A buffer for the rejects of a chunk, that can still be read once the parser owns it.
+#[derive(Clone, Default)]
+struct RejectsBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for RejectsBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Parses chunks until the chunk sender is dropped.
+fn parse_chunks(
+    chunk_receiver: &Mutex<Receiver<Chunk>>,
+    result_sender: &Sender<ChunkResult>,
+    mode: Mode,
+    selection: &Selection,
+    keep_rejects: bool,
+) {
+    loop {
+        // The lock is only poisoned if another worker panicked
+        let chunk = match chunk_receiver.lock().map(|receiver| receiver.recv()) {
+            Ok(Ok(chunk)) => chunk,
+            _ => return,
+        };
+        let result = parse_chunk(&chunk, mode, selection, keep_rejects);
+        if result_sender.send(result).is_err() {
+            return;
+        }
+    }
+}
+
+fn parse_chunk(
+    chunk: &Chunk,
+    mode: Mode,
+    selection: &Selection,
+    keep_rejects: bool,
+) -> ChunkResult {
+    let rejects = RejectsBuffer::default();
+    let mut parser = Parser::new(&chunk.data[..])
+        .with_mode(mode)
+        .with_selection(selection.clone());
+    if keep_rejects {
+        parser = parser.with_rejects(rejects.clone());
+    }
+    let mut feature_members = parser.into_iter();
+    feature_members.collector.resolver.record_unresolved();
+
+    let mut result = ChunkResult {
+        index: chunk.index,
+        feature_members: Vec::new(),
+        error: None,
+        resolver: ReferenceResolver::default(),
+        skipped: BTreeMap::new(),
+        rejects: Vec::new(),
+    };
+    while let Some(feature_member) = feature_members.next() {
+        match feature_member {
+            Ok(feature_member) => {
+                let unresolved = feature_members.collector.resolver.take_unresolved();
+                result.feature_members.push((feature_member, unresolved));
+            }
+            Err(error) => result.error = Some(chunk.relocate(error)),
+        }
+    }
+    result.resolver = std::mem::take(&mut feature_members.collector.resolver);
+    result.skipped = std::mem::take(&mut feature_members.skipped);
+    drop(feature_members);
+    result.rejects = rejects.0.take();
+    result
+}
+
+/// Keeps a copy of everything that is read from `reader`.
+struct RecordingReader<R> {
+    reader: R,
+    recorded: Vec<u8>,
+}
+
+impl<R> Read for RecordingReader<R>
+where
+    R: BufRead,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.reader.read(buf)?;
+        self.recorded.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+}
+
+impl<R> BufRead for RecordingReader<R>
+where
+    R: BufRead,
+{
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.reader.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        // What is consumed has been filled already, so this doesn't read
+        if let Ok(data) = self.reader.fill_buf() {
+            self.recorded.extend_from_slice(&data[..amt]);
+        }
+        self.reader.consume(amt);
+    }
+}
+
+/// The names of the open elements, as they are written in the document.
+#[derive(Clone, Default, PartialEq, Eq)]
+struct OpenElements {
+    names: Vec<u8>,
+    /// Where every name ends in `names`.
+    ends: Vec<usize>,
+}
+
+impl OpenElements {
+    fn push(&mut self, name: &[u8]) {
+        self.names.extend_from_slice(name);
+        self.ends.push(self.names.len());
+    }
+
+    fn pop(&mut self) {
+        self.ends.pop();
+        self.names.truncate(self.ends.last().copied().unwrap_or(0));
+    }
+
+    fn last(&self) -> Option<&[u8]> {
+        let end = *self.ends.last()?;
+        let start = match self.ends.len() {
+            1 => 0,
+            len => self.ends[len - 2],
+        };
+        Some(&self.names[start..end])
+    }
+
+    /// Returns the end tags that close the open elements, innermost first.
+    fn end_tags(&self) -> Vec<u8> {
+        let mut end_tags = Vec::new();
+        let mut end = self.names.len();
+        for start in self.ends.iter().rev().skip(1).chain([&0]) {
+            end_tags.extend_from_slice(b"</");
+            end_tags.extend_from_slice(&self.names[*start..end]);
+            end_tags.push(b'>');
+            end = *start;
+        }
+        end_tags
+    }
+}
+
+/// Returns the part of a name after its prefix.
+fn local_name(name: &[u8]) -> &[u8] {
+    name.iter()
+        .rposition(|&b| b == b':')
+        .map_or(name, |colon| &name[colon + 1..])
+}
+
+/// Splits a document into chunks.
+struct ChunkReader<R> {
+    reader: Reader<RecordingReader<R>>,
+    chunk_size: usize,
+    /// Everything before the first feature member.
+    header: Vec<u8>,
+    /// End tags for the elements that are still open at the end of the header.
+    footer: Vec<u8>,
+    /// The elements that are open around the first feature member, once the header has been read.
+    /// The document is only split before feature members that have these same elements around
+    /// them, so that the header and footer fit every chunk.
+    member_parents: Option<OpenElements>,
+    open: OpenElements,
+    event_buf: Vec<u8>,
+    /// Where the recorded bytes that are not yet in a chunk start in the document.
+    offset: u64,
+    /// The number of line breaks before `offset`.
+    line_breaks: u64,
+    done: bool,
+    /// The error that ended the document, returned after the last chunk.
+    error: Option<Error>,
+}
+
+impl<R> ChunkReader<R>
+where
+    R: BufRead,
+{
+    fn new(reader: R, chunk_size: usize) -> Self {
+        ChunkReader {
+            reader: Reader::from_reader(RecordingReader {
+                reader,
+                recorded: Vec::new(),
+            }),
+            chunk_size,
+            header: Vec::new(),
+            footer: Vec::new(),
+            member_parents: None,
+            open: OpenElements::default(),
+            event_buf: Vec::new(),
+            offset: 0,
+            line_breaks: 0,
+            done: false,
+            error: None,
+        }
+    }
+
+    fn next_chunk(&mut self, index: usize) -> Result<Option<Chunk>> {
+        if self.done {
+            return self.error.take().map_or(Ok(None), Err);
+        }
+        if self.member_parents.is_none() && !self.read_header()? {
+            // Without feature members the whole document is one chunk
+            self.done = true;
+            let data = std::mem::take(&mut self.reader.get_mut().recorded);
+            return Ok(Some(Chunk {
+                index,
+                header_len: 0,
+                offset: 0,
+                line_breaks: 0,
+                data,
+            }));
+        }
+
+        // Where the feature member that is being read starts
+        let mut member_start = 0;
+        loop {
+            let position = match self.next_member() {
+                Ok(position) => position,
+                Err(error) => {
+                    // The feature members before the error still make a chunk
+                    self.done = true;
+                    self.error = Some(error);
+                    return Ok(Some(self.chunk(index, member_start, true)));
+                }
+            };
+            let Some(position) = position else {
+                // The last chunk has the end tags of the document itself
+                self.done = true;
+                let end = self.reader.get_ref().recorded.len();
+                return Ok(Some(self.chunk(index, end, false)));
+            };
+            // The chunk starts with a feature member, which must not end it
+            let end = (position - self.offset) as usize;
+            if end > self.chunk_size {
+                return Ok(Some(self.chunk(index, end, true)));
+            }
+            member_start = end;
+        }
+    }
+
+    /// Makes a chunk of the first `end` recorded bytes.
+    fn chunk(&mut self, index: usize, end: usize, add_footer: bool) -> Chunk {
+        let recorded = &mut self.reader.get_mut().recorded;
+        let chunk_line_breaks = line_breaks(&recorded[..end]);
+        let mut data = Vec::with_capacity(self.header.len() + end + self.footer.len());
+        data.extend_from_slice(&self.header);
+        data.extend(recorded.drain(..end));
+        if add_footer {
+            data.extend_from_slice(&self.footer);
+        }
+        let chunk = Chunk {
+            index,
+            data,
+            header_len: self.header.len(),
+            offset: self.offset,
+            line_breaks: self.line_breaks,
+        };
+        self.offset += end as u64;
+        self.line_breaks += chunk_line_breaks;
+        chunk
+    }
+
+    /// Reads up to the first feature member, returning whether there is one.
+    fn read_header(&mut self) -> Result<bool> {
+        let Some(header_len) = self.next_member()? else {
+            return Ok(false);
+        };
+        self.header = self
+            .reader
+            .get_mut()
+            .recorded
+            .drain(..header_len as usize)
+            .collect();
+        self.offset = header_len;
+        self.line_breaks = line_breaks(&self.header);
+        let mut member_parents = self.open.clone();
+        member_parents.pop();
+        self.footer = member_parents.end_tags();
+        self.member_parents = Some(member_parents);
+        Ok(true)
+    }
+
+    /// Reads up to and including the start tag of the next feature member that can start a chunk,
+    /// returning where it starts in the document, or `None` at the end of the document.
+    ///
+    /// Before the header has been read, a feature member is any child of a member property other
+    /// than a nested collection.
+    fn next_member(&mut self) -> Result<Option<u64>> {
+        loop {
+            let position = self.reader.buffer_position();
+            self.event_buf.clear();
+            let event = match self.reader.read_event_into(&mut self.event_buf) {
+                Ok(event) => event,
+                Err(source) => {
+                    // The error is on the line the event starts on, as in the parser
+                    let recorded = &self.reader.get_ref().recorded;
+                    let before = &recorded[..(position - self.offset) as usize];
+                    return Err(Error::Xml {
+                        position: self.reader.error_position(),
+                        line: self.line_breaks + line_breaks(before) + 1,
+                        source,
+                    });
+                }
+            };
+            match event {
+                Event::Start(e) => {
+                    let is_member = match &self.member_parents {
+                        Some(member_parents) => self.open == *member_parents,
+                        None => {
+                            self.open.last().is_some_and(|parent| {
+                                MEMBER_PROPERTIES.contains(&local_name(parent))
+                            }) && !COLLECTIONS.contains(&local_name(e.name().as_ref()))
+                        }
+                    };
+                    self.open.push(e.name().as_ref());
+                    if is_member {
+                        return Ok(Some(position));
+                    }
+                }
+                Event::End(_) => self.open.pop(),
+                Event::Eof => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use super::FeatureMemberType;
+use crate::{FeatureMember, Reference};
+
+/// Fragments of identifiers that tell which kind of feature they identify, checked in order
+/// against the identifier with case and punctuation removed.
+const TYPE_HINTS: [(&str, FeatureMemberType); 7] = [
+    ("postaldescriptor", FeatureMemberType::PostalDescriptor),
+    ("thoroughfarename", FeatureMemberType::ThoroughfareName),
+    ("addressareaname", FeatureMemberType::AddressAreaName),
+    ("adminunitname", FeatureMemberType::AdminUnitName),
+    ("thoroughfare", FeatureMemberType::ThoroughfareName),
+    ("addressarea", FeatureMemberType::AddressAreaName),
+    ("adminunit", FeatureMemberType::AdminUnitName),
+];
+
+/// Resolves `xlink:href` values to the type and local ID of the feature they refer to.
+///
+/// References to features earlier in the same document (`#gml-id`) are resolved exactly. Other
+/// references, including local ones to features that only appear later, are classified by the
+/// feature type named in the identifier, which is how most INSPIRE publishers build their
+/// `gml:id`s, URNs and HTTP URIs.
+#[derive(Default)]
+pub(crate) struct ReferenceResolver {
+    features: HashMap<String, (FeatureMemberType, String)>,
+    /// `#gml-id` references that could not be resolved exactly, if these are being recorded.
+    unresolved: Option<RefCell<Vec<String>>>,
+}
+
+impl ReferenceResolver {
+    /// Records the `gml:id` of a feature, if it has one, so that later references to it can be
+    /// resolved.
+    pub(crate) fn register(&mut self, feature_member: &FeatureMember<'_>) {
+        let (feature_type, local_id, identifier) = match feature_member {
+            FeatureMember::Address { .. } => return,
+            FeatureMember::AdminUnitName {
+                local_id,
+                identifier,
+                ..
+            } => (FeatureMemberType::AdminUnitName, local_id, identifier),
+            FeatureMember::AddressAreaName {
+                local_id,
+                identifier,
+                ..
+            } => (FeatureMemberType::AddressAreaName, local_id, identifier),
+            FeatureMember::ThoroughfareName {
+                local_id,
+                identifier,
+                ..
+            } => (FeatureMemberType::ThoroughfareName, local_id, identifier),
+            FeatureMember::PostalDescriptor {
+                local_id,
+                identifier,
+                ..
+            } => (FeatureMemberType::PostalDescriptor, local_id, identifier),
+        };
+        if let Some(gml_id) = &identifier.gml_id {
+            self.features
+                .insert(gml_id.clone(), (feature_type, local_id.to_string()));
+        }
+    }
+
+    /// Returns the type of the referenced feature, if it can be determined, and the reference.
+    pub(crate) fn resolve(&self, href: String) -> (Option<FeatureMemberType>, Reference) {
+        if let Some(gml_id) = href.strip_prefix('#')
+            && let Some((feature_type, local_id)) = self.features.get(gml_id)
+        {
+            let local_id = local_id.clone();
+            return (Some(*feature_type), Reference { href, local_id });
+        }
+
+        if href.starts_with('#')
+            && let Some(unresolved) = &self.unresolved
+        {
+            unresolved.borrow_mut().push(href.clone());
+        }
+        let identifier = href.strip_prefix('#').unwrap_or(&href);
+        let (feature_type, local_id) = classify(identifier);
+        let local_id = local_id.to_string();
+        (feature_type, Reference { href, local_id })
+    }
+
+    /// Starts recording the `#gml-id` references that are not resolved exactly, so that they can
+    /// be resolved again once more of the document is known.
+    pub(crate) fn record_unresolved(&mut self) {
+        self.unresolved = Some(RefCell::default());
+    }
+
+    /// Returns the references recorded since the last call.
+    pub(crate) fn take_unresolved(&mut self) -> Vec<String> {
+        self.unresolved
+            .as_mut()
+            .map(|unresolved| std::mem::take(unresolved.get_mut()))
+            .unwrap_or_default()
+    }
+
+    /// Adds the features registered with `other`.
+    pub(crate) fn extend(&mut self, other: ReferenceResolver) {
+        self.features.extend(other.features);
+    }
+
+    /// Resolves `href` in `feature_member` again, if it refers to a registered feature, moving
+    /// an address component to the field for the type of that feature.
+    pub(crate) fn patch(&self, feature_member: &mut FeatureMember<'_>, href: &str) {
+        let Some((feature_type, local_id)) = href
+            .strip_prefix('#')
+            .and_then(|gml_id| self.features.get(gml_id))
+        else {
+            return;
+        };
+        let reference = Reference {
+            href: href.to_string(),
+            local_id: local_id.clone(),
+        };
+        let refers_to_href =
+            |field: &Option<Reference>| field.as_ref().is_some_and(|field| field.href == href);
+
+        match feature_member {
+            FeatureMember::Address {
+                admin_unit_ref,
+                address_area_ref,
+                thoroughfare_ref,
+                postal_descriptor_ref,
+                other_component_refs,
+                ..
+            } => {
+                other_component_refs.retain(|other| other.href != href);
+                for field in [
+                    &mut *admin_unit_ref,
+                    &mut *address_area_ref,
+                    &mut *thoroughfare_ref,
+                    &mut *postal_descriptor_ref,
+                ] {
+                    if refers_to_href(field) {
+                        *field = None;
+                    }
+                }
+                let field = match feature_type {
+                    FeatureMemberType::AdminUnitName => admin_unit_ref,
+                    FeatureMemberType::AddressAreaName => address_area_ref,
+                    FeatureMemberType::ThoroughfareName => thoroughfare_ref,
+                    FeatureMemberType::PostalDescriptor => postal_descriptor_ref,
+                    // Addresses aren't registered, but would be a component of unknown type
+                    FeatureMemberType::Address => {
+                        other_component_refs.push(reference);
+                        return;
+                    }
+                };
+                *field = Some(reference);
+            }
+            FeatureMember::AddressAreaName {
+                situated_in_ref, ..
+            }
+            | FeatureMember::ThoroughfareName {
+                situated_in_ref, ..
+            }
+            | FeatureMember::PostalDescriptor {
+                admin_unit_ref: situated_in_ref,
+                ..
+            } => {
+                if refers_to_href(situated_in_ref) {
+                    *situated_in_ref = Some(reference);
+                }
+            }
+            FeatureMember::AdminUnitName { .. } => {}
+        }
+    }
+}
+
+/// Finds the feature type named in an identifier, and takes the local ID to be whatever follows
+/// it. Without a type, the local ID is the last segment of the identifier.
+fn classify(identifier: &str) -> (Option<FeatureMemberType>, &str) {
+    let (normalized, offsets): (String, Vec<usize>) = identifier
+        .char_indices()
+        .filter(|(_, c)| c.is_alphanumeric())
+        .map(|(offset, c)| (c.to_ascii_lowercase(), offset + c.len_utf8()))
+        .unzip();
+
+    for (hint, feature_type) in TYPE_HINTS {
+        if let Some(start) = normalized.find(hint) {
+            let end = offsets[normalized[..start + hint.len()].chars().count() - 1];
+            let local_id = identifier[end..].trim_start_matches(|c: char| !c.is_alphanumeric());
+            if !local_id.is_empty() {
+                return (Some(feature_type), local_id);
+            }
+        }
+    }
+
+    let local_id = identifier
+        .rsplit(['#', '/', ':', '.'])
+        .find(|segment| !segment.is_empty())
+        .unwrap_or(identifier);
+    (None, local_id)
+}
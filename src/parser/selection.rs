@@ -0,0 +1,109 @@
+//! Choosing which feature members, and which of their properties, the parser reads.
+
+use std::str::FromStr;
+
+/// The types of feature member the parser knows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeatureMemberType {
+    Address,
+    AdminUnitName,
+    AddressAreaName,
+    ThoroughfareName,
+    PostalDescriptor,
+}
+
+impl FeatureMemberType {
+    /// Returns the type of a feature member from its local name in the `ad` namespace.
+    pub(crate) fn from_local_name(local_name: &str) -> Option<FeatureMemberType> {
+        match local_name {
+            "Address" => Some(FeatureMemberType::Address),
+            "AdminUnitName" => Some(FeatureMemberType::AdminUnitName),
+            "AddressAreaName" => Some(FeatureMemberType::AddressAreaName),
+            "ThoroughfareName" => Some(FeatureMemberType::ThoroughfareName),
+            "PostalDescriptor" => Some(FeatureMemberType::PostalDescriptor),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for FeatureMemberType {
+    type Err = String;
+
+    /// Parses a type written as in the document, e.g. `ad:Address` or `Address`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FeatureMemberType::from_local_name(s.strip_prefix("ad:").unwrap_or(s))
+            .ok_or_else(|| format!("Unknown feature type: {}", s))
+    }
+}
+
+/// Properties of feature members that can be left out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Field {
+    /// The `ad:position` of addresses.
+    Position,
+    /// The `ad:locator`s of addresses, which also leaves the house number and the other
+    /// designator columns empty.
+    Locators,
+    /// The `ad:name`s of admin unit, address area and thoroughfare names, and the `ad:postName`
+    /// of postal descriptors.
+    Names,
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "position" => Ok(Field::Position),
+            "locators" => Ok(Field::Locators),
+            "names" => Ok(Field::Names),
+            _ => Err(format!("Unknown field: {}", s)),
+        }
+    }
+}
+
+/// The feature members, and the properties of them, that the parser reads.
+///
+/// Everything else is skipped as soon as its start tag has been read, without visiting the
+/// elements in it. References to features that are skipped can only be resolved from their
+/// identifiers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Selection {
+    /// The selected types, or `None` for all of them.
+    feature_types: Option<Vec<FeatureMemberType>>,
+    skipped_fields: Vec<Field>,
+}
+
+impl Selection {
+    /// Selects every feature member with all of its properties.
+    pub fn all() -> Self {
+        Selection::default()
+    }
+
+    /// Only reads feature members of these types.
+    pub fn with_feature_types(
+        mut self,
+        feature_types: impl IntoIterator<Item = FeatureMemberType>,
+    ) -> Self {
+        self.feature_types = Some(feature_types.into_iter().collect());
+        self
+    }
+
+    /// Leaves `field` out of the feature members that have it.
+    pub fn without(mut self, field: Field) -> Self {
+        if !self.skipped_fields.contains(&field) {
+            self.skipped_fields.push(field);
+        }
+        self
+    }
+
+    pub fn includes(&self, feature_type: FeatureMemberType) -> bool {
+        self.feature_types
+            .as_ref()
+            .is_none_or(|feature_types| feature_types.contains(&feature_type))
+    }
+
+    pub fn includes_field(&self, field: Field) -> bool {
+        !self.skipped_fields.contains(&field)
+    }
+}
@@ -0,0 +1,435 @@
+//! Joining addresses with the names they refer to.
+//!
+//! [`ResolvingEmitter`] sits between the parser and an emitter of [`ResolvedAddress`]es. It keeps
+//! the names of streets, cities, municipalities and postcodes in memory, and turns every address
+//! into a record with those names filled in. Addresses that refer to a feature that has not been
+//! seen yet, by local ID or by `gml:id`, are written to a spill file, and resolved once the whole
+//! document has been read.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{
+    FeatureMember, Reference,
+    emitter::{FeatureMemberEmitter, write_tsv_field},
+    error::{Error, Result},
+    parser::FeatureMemberType,
+};
+
+/// An address with the names it refers to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResolvedAddress {
+    pub local_id: String,
+    /// The name of the thoroughfare.
+    pub street: Option<String>,
+    pub number: Option<String>,
+    pub number_extension: Option<String>,
+    pub number_2nd_extension: Option<String>,
+    /// The postal delivery identifier of the address, or else the post code of its postal
+    /// descriptor.
+    pub postcode: Option<String>,
+    /// The name of the address area.
+    pub city: Option<String>,
+    /// The name of the admin unit of the address, or else that of its address area.
+    pub municipality: Option<String>,
+}
+
+pub trait ResolvedAddressEmitter {
+    fn emit(&mut self, address: ResolvedAddress) -> Result<()>;
+    fn start(&mut self) -> Result<()> {
+        Ok(())
+    }
+    fn end(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Resolves addresses and passes them to another emitter.
+///
+/// Addresses are emitted as soon as everything they refer to has been seen, so with the names
+/// before the addresses, as in the Dutch BAG, nothing is spilled and addresses keep the order of
+/// the document. Spilled addresses are emitted at the end.
+pub struct ResolvingEmitter<E> {
+    inner: E,
+    /// Names per local ID. A feature without a name is `None`.
+    admin_units: HashMap<String, Option<String>>,
+    thoroughfares: HashMap<String, Option<String>>,
+    /// Names and admin unit local IDs per local ID.
+    address_areas: HashMap<String, (Option<String>, Option<String>)>,
+    /// Post codes per local ID.
+    postal_descriptors: HashMap<String, Option<String>>,
+    /// Types and local IDs of the names per `gml:id`, to classify components the parser could
+    /// not.
+    gml_ids: HashMap<String, (FeatureMemberType, String)>,
+    spill_dir: PathBuf,
+    spill: Option<SpillFile>,
+}
+
+/// The spill file, which is removed when dropped.
+struct SpillFile {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// An address with the local IDs of the features it refers to, as it is spilled.
+struct PendingAddress {
+    local_id: String,
+    number: Option<String>,
+    number_extension: Option<String>,
+    number_2nd_extension: Option<String>,
+    postal_delivery_identifier: Option<String>,
+    admin_unit: Option<String>,
+    address_area: Option<String>,
+    thoroughfare: Option<String>,
+    postal_descriptor: Option<String>,
+    /// The hrefs of `#gml-id` components of unknown type.
+    other_components: Vec<String>,
+}
+
+impl<E> ResolvingEmitter<E>
+where
+    E: ResolvedAddressEmitter,
+{
+    pub fn new(inner: E) -> Self {
+        ResolvingEmitter {
+            inner,
+            admin_units: HashMap::new(),
+            thoroughfares: HashMap::new(),
+            address_areas: HashMap::new(),
+            postal_descriptors: HashMap::new(),
+            gml_ids: HashMap::new(),
+            spill_dir: std::env::temp_dir(),
+            spill: None,
+        }
+    }
+
+    /// Sets the directory for the spill file, which is the system's temporary directory by
+    /// default.
+    pub fn with_spill_dir(mut self, spill_dir: impl Into<PathBuf>) -> Self {
+        self.spill_dir = spill_dir.into();
+        self
+    }
+
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+
+    /// Moves the components of unknown type that refer to a known `gml:id` to the field for the
+    /// type of that feature.
+    fn classify(&self, address: &mut PendingAddress) {
+        address.other_components.retain(|href| {
+            let Some((feature_type, local_id)) = href
+                .strip_prefix('#')
+                .and_then(|gml_id| self.gml_ids.get(gml_id))
+            else {
+                return true;
+            };
+            let field = match feature_type {
+                FeatureMemberType::AdminUnitName => &mut address.admin_unit,
+                FeatureMemberType::AddressAreaName => &mut address.address_area,
+                FeatureMemberType::ThoroughfareName => &mut address.thoroughfare,
+                FeatureMemberType::PostalDescriptor => &mut address.postal_descriptor,
+                FeatureMemberType::Address => return true,
+            };
+            field.get_or_insert_with(|| local_id.clone());
+            false
+        });
+    }
+
+    fn is_resolvable(&self, address: &PendingAddress) -> bool {
+        fn known<V>(local_id: &Option<String>, features: &HashMap<String, V>) -> bool {
+            local_id
+                .as_ref()
+                .is_none_or(|local_id| features.contains_key(local_id))
+        }
+        let area_admin_unit = match (&address.admin_unit, &address.address_area) {
+            (None, Some(address_area)) => self
+                .address_areas
+                .get(address_area)
+                .and_then(|(_, admin_unit)| admin_unit.clone()),
+            _ => None,
+        };
+        address.other_components.is_empty()
+            && known(&address.admin_unit, &self.admin_units)
+            && known(&address.address_area, &self.address_areas)
+            && known(&address.thoroughfare, &self.thoroughfares)
+            && known(&address.postal_descriptor, &self.postal_descriptors)
+            && known(&area_admin_unit, &self.admin_units)
+    }
+
+    fn resolve(&self, address: PendingAddress) -> ResolvedAddress {
+        let name = |local_id: Option<&String>, features: &HashMap<String, Option<String>>| {
+            local_id.and_then(|local_id| features.get(local_id).cloned().flatten())
+        };
+        let address_area = address
+            .address_area
+            .as_ref()
+            .and_then(|local_id| self.address_areas.get(local_id));
+        let admin_unit = address
+            .admin_unit
+            .as_ref()
+            .or(address_area.and_then(|(_, admin_unit)| admin_unit.as_ref()));
+
+        ResolvedAddress {
+            street: name(address.thoroughfare.as_ref(), &self.thoroughfares),
+            postcode: address
+                .postal_delivery_identifier
+                .or_else(|| name(address.postal_descriptor.as_ref(), &self.postal_descriptors)),
+            city: address_area.and_then(|(name, _)| name.clone()),
+            municipality: name(admin_unit, &self.admin_units),
+            local_id: address.local_id,
+            number: address.number,
+            number_extension: address.number_extension,
+            number_2nd_extension: address.number_2nd_extension,
+        }
+    }
+
+    fn register(
+        &mut self,
+        feature_type: FeatureMemberType,
+        local_id: &str,
+        gml_id: Option<String>,
+    ) {
+        if let Some(gml_id) = gml_id {
+            self.gml_ids
+                .insert(gml_id, (feature_type, local_id.to_string()));
+        }
+    }
+
+    fn spill(&mut self, address: &PendingAddress) -> Result<()> {
+        static SPILL_FILES: AtomicU64 = AtomicU64::new(0);
+
+        if self.spill.is_none() {
+            let path = self.spill_dir.join(format!(
+                "gmlparser-{}-{}.spill",
+                std::process::id(),
+                SPILL_FILES.fetch_add(1, Ordering::Relaxed)
+            ));
+            let file = File::create_new(&path)?;
+            self.spill = Some(SpillFile {
+                path,
+                writer: BufWriter::new(file),
+            });
+        }
+        let writer = &mut self.spill.as_mut().unwrap().writer;
+
+        let fields = [
+            Some(&address.local_id),
+            address.number.as_ref(),
+            address.number_extension.as_ref(),
+            address.number_2nd_extension.as_ref(),
+            address.postal_delivery_identifier.as_ref(),
+            address.admin_unit.as_ref(),
+            address.address_area.as_ref(),
+            address.thoroughfare.as_ref(),
+            address.postal_descriptor.as_ref(),
+        ]
+        .into_iter()
+        .chain(address.other_components.iter().map(Some));
+        for (index, field) in fields.enumerate() {
+            if index > 0 {
+                writer.write_all(b"\t")?;
+            }
+            write_field(writer, field.map(String::as_str))?;
+        }
+        writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    /// Resolves and emits the spilled addresses, removing the spill file.
+    fn emit_spilled(&mut self) -> Result<()> {
+        let Some(mut spill) = self.spill.take() else {
+            return Ok(());
+        };
+        spill.writer.flush()?;
+        for line in BufReader::new(File::open(&spill.path)?).lines() {
+            let mut address = read_pending_address(&line?)?;
+            self.classify(&mut address);
+            let address = self.resolve(address);
+            self.inner.emit(address)?;
+        }
+        Ok(())
+    }
+}
+
+impl<E> FeatureMemberEmitter for ResolvingEmitter<E>
+where
+    E: ResolvedAddressEmitter,
+{
+    fn emit(&mut self, feature_member: FeatureMember<'_>) -> Result<()> {
+        let local_id = |reference: Option<Reference>| reference.map(|reference| reference.local_id);
+        let owned = |text: Option<std::borrow::Cow<'_, str>>| text.map(|text| text.into_owned());
+
+        match feature_member {
+            FeatureMember::Address {
+                local_id: address_local_id,
+                number,
+                number_extension,
+                number_2nd_extension,
+                postal_delivery_identifier,
+                admin_unit_ref,
+                address_area_ref,
+                thoroughfare_ref,
+                postal_descriptor_ref,
+                other_component_refs,
+                ..
+            } => {
+                let mut address = PendingAddress {
+                    local_id: address_local_id.into_owned(),
+                    number: owned(number),
+                    number_extension: owned(number_extension),
+                    number_2nd_extension: owned(number_2nd_extension),
+                    postal_delivery_identifier: owned(postal_delivery_identifier),
+                    admin_unit: local_id(admin_unit_ref),
+                    address_area: local_id(address_area_ref),
+                    thoroughfare: local_id(thoroughfare_ref),
+                    postal_descriptor: local_id(postal_descriptor_ref),
+                    // Other components can only be classified by a `gml:id` that is still to come
+                    other_components: other_component_refs
+                        .into_iter()
+                        .map(|reference| reference.href)
+                        .filter(|href| href.starts_with('#'))
+                        .collect(),
+                };
+                self.classify(&mut address);
+                if self.is_resolvable(&address) {
+                    let address = self.resolve(address);
+                    self.inner.emit(address)?;
+                } else {
+                    self.spill(&address)?;
+                }
+            }
+            FeatureMember::AdminUnitName {
+                local_id,
+                name: admin_unit_name,
+                identifier,
+                ..
+            } => {
+                self.register(
+                    FeatureMemberType::AdminUnitName,
+                    &local_id,
+                    identifier.gml_id,
+                );
+                self.admin_units
+                    .insert(local_id.into_owned(), owned(admin_unit_name));
+            }
+            FeatureMember::AddressAreaName {
+                local_id: address_area_local_id,
+                name: address_area_name,
+                situated_in_ref,
+                identifier,
+                ..
+            } => {
+                self.register(
+                    FeatureMemberType::AddressAreaName,
+                    &address_area_local_id,
+                    identifier.gml_id,
+                );
+                self.address_areas.insert(
+                    address_area_local_id.into_owned(),
+                    (owned(address_area_name), local_id(situated_in_ref)),
+                );
+            }
+            FeatureMember::ThoroughfareName {
+                local_id,
+                name: thoroughfare_name,
+                identifier,
+                ..
+            } => {
+                self.register(
+                    FeatureMemberType::ThoroughfareName,
+                    &local_id,
+                    identifier.gml_id,
+                );
+                self.thoroughfares
+                    .insert(local_id.into_owned(), owned(thoroughfare_name));
+            }
+            FeatureMember::PostalDescriptor {
+                local_id,
+                post_code,
+                identifier,
+                ..
+            } => {
+                self.register(
+                    FeatureMemberType::PostalDescriptor,
+                    &local_id,
+                    identifier.gml_id,
+                );
+                self.postal_descriptors
+                    .insert(local_id.into_owned(), owned(post_code));
+            }
+        }
+        Ok(())
+    }
+
+    fn start(&mut self) -> Result<()> {
+        self.inner.start()
+    }
+
+    fn end(&mut self) -> Result<()> {
+        self.emit_spilled()?;
+        self.inner.end()
+    }
+}
+
+/// Writes a spill file field, escaped as in [`write_tsv_field`], and writing `None` as `\N`.
+fn write_field(writer: &mut impl Write, field: Option<&str>) -> std::io::Result<()> {
+    match field {
+        Some(field) => write_tsv_field(writer, field),
+        None => writer.write_all(b"\\N"),
+    }
+}
+
+fn read_field(field: &str) -> Option<String> {
+    if field == "\\N" {
+        return None;
+    }
+    let mut value = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => value.push('\t'),
+            Some('n') => value.push('\n'),
+            Some('r') => value.push('\r'),
+            Some(c) => value.push(c),
+            None => {}
+        }
+    }
+    Some(value)
+}
+
+fn read_pending_address(line: &str) -> Result<PendingAddress> {
+    let mut fields = line.split('\t').map(read_field);
+    let mut next = || fields.next().flatten();
+    let local_id = next().ok_or_else(|| {
+        Error::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "spilled address without a local ID",
+        ))
+    })?;
+    Ok(PendingAddress {
+        local_id,
+        number: next(),
+        number_extension: next(),
+        number_2nd_extension: next(),
+        postal_delivery_identifier: next(),
+        admin_unit: next(),
+        address_area: next(),
+        thoroughfare: next(),
+        postal_descriptor: next(),
+        other_components: fields.flatten().collect(),
+    })
+}
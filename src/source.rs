@@ -0,0 +1,190 @@
+//! Opening GML documents that may be compressed, without unpacking them to disk, and that may be
+//! in another encoding than UTF-8.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE};
+use encoding_rs_io::DecodeReaderBytesBuilder;
+use memmap2::Mmap;
+
+use crate::error::{Error, Result};
+
+/// How a file is compressed, as detected from its first bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    /// A zip archive, which may hold more than one document.
+    Zip,
+}
+
+impl Compression {
+    pub fn detect(magic: &[u8]) -> Compression {
+        if magic.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else if magic.starts_with(b"PK\x03\x04") || magic.starts_with(b"PK\x05\x06") {
+            Compression::Zip
+        } else {
+            Compression::None
+        }
+    }
+}
+
+/// The encoding of a document, as detected from its first bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DocumentEncoding {
+    pub encoding: &'static Encoding,
+    /// The length of the byte-order mark the document starts with, if any.
+    pub bom_length: usize,
+}
+
+impl DocumentEncoding {
+    /// Detects the encoding from the byte-order mark, or else from the `encoding` of the XML
+    /// declaration, defaulting to UTF-8. An explicit `encoding` overrides both, but a byte-order
+    /// mark is still skipped.
+    pub fn detect(start: &[u8], encoding: Option<&'static Encoding>) -> Result<DocumentEncoding> {
+        let bom = Encoding::for_bom(start);
+        let bom_length = bom.map_or(0, |(_, length)| length);
+        let encoding = match (encoding, bom) {
+            (Some(encoding), _) => encoding,
+            (None, Some((encoding, _))) => encoding,
+            // UTF-16 without a byte-order mark, recognised by how `<?` is encoded
+            (None, None) if start.starts_with(b"<\0?\0") => UTF_16LE,
+            (None, None) if start.starts_with(b"\0<\0?") => UTF_16BE,
+            (None, None) => match declared_encoding(start) {
+                Some(label) => match Encoding::for_label(label.as_bytes()) {
+                    // A declaration that could be read as ASCII can't be right about UTF-16
+                    Some(encoding) if !encoding.is_ascii_compatible() => UTF_8,
+                    Some(encoding) => encoding,
+                    None => return Err(Error::UnknownEncoding(label.to_string())),
+                },
+                None => UTF_8,
+            },
+        };
+        Ok(DocumentEncoding {
+            encoding,
+            bom_length,
+        })
+    }
+}
+
+/// Returns the `encoding` pseudo-attribute of the XML declaration at the start of `start`.
+fn declared_encoding(start: &[u8]) -> Option<&str> {
+    let declaration = start.strip_prefix(b"<?xml")?;
+    let end = declaration.windows(2).position(|window| window == b"?>")?;
+    let declaration = std::str::from_utf8(&declaration[..end]).ok()?;
+    let (_, value) = declaration.split_once("encoding")?;
+    let value = value.trim_start().strip_prefix('=')?.trim_start();
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let (label, _) = value[1..].split_once(quote)?;
+    Some(label)
+}
+
+/// Returns a reader that yields the document in `reader` as UTF-8, without its byte-order mark.
+///
+/// A document in another encoding is decoded as it is read, so byte positions in errors are
+/// positions in the decoded text. Bytes that are not valid in the encoding are replaced by U+FFFD.
+pub fn decode<'r>(
+    mut reader: impl BufRead + 'r,
+    encoding: Option<&'static Encoding>,
+) -> Result<Box<dyn BufRead + 'r>> {
+    let detected = DocumentEncoding::detect(reader.fill_buf()?, encoding)?;
+    reader.consume(detected.bom_length);
+    if detected.encoding == UTF_8 {
+        return Ok(Box::new(reader));
+    }
+    Ok(Box::new(BufReader::new(
+        DecodeReaderBytesBuilder::new()
+            .encoding(Some(detected.encoding))
+            .build(reader),
+    )))
+}
+
+/// Calls `f` with a reader for every GML document in the file at `path`, decoded to UTF-8 with
+/// [`decode`].
+///
+/// A plain, gzip or zstd-compressed file is a single document, for which `f` is called with no
+/// name. For a zip archive, `f` is called with the name and contents of every `.gml` entry, in the
+/// order they appear in the archive.
+pub fn for_each_document<F>(
+    path: &Path,
+    encoding: Option<&'static Encoding>,
+    mut f: F,
+) -> Result<()>
+where
+    F: FnMut(Option<&str>, &mut dyn BufRead) -> Result<()>,
+{
+    let mut reader = BufReader::new(File::open(path)?);
+    match Compression::detect(reader.fill_buf()?) {
+        Compression::None => f(None, &mut decode(reader, encoding)?),
+        Compression::Gzip => f(
+            None,
+            &mut decode(
+                BufReader::new(flate2::bufread::MultiGzDecoder::new(reader)),
+                encoding,
+            )?,
+        ),
+        Compression::Zstd => f(
+            None,
+            &mut decode(
+                BufReader::new(zstd::stream::read::Decoder::with_buffer(reader)?),
+                encoding,
+            )?,
+        ),
+        Compression::Zip => {
+            let mut archive = zip::ZipArchive::new(reader.into_inner())?;
+            for index in 0..archive.len() {
+                let entry = archive.by_index(index)?;
+                if !entry.is_file() || !entry.name().to_ascii_lowercase().ends_with(".gml") {
+                    continue;
+                }
+                let name = entry.name().to_string();
+                f(Some(&name), &mut decode(BufReader::new(entry), encoding)?)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// A UTF-8 document mapped into memory.
+pub struct MappedDocument {
+    mmap: Mmap,
+    bom_length: usize,
+}
+
+impl MappedDocument {
+    /// The document, without its byte-order mark.
+    pub fn bytes(&self) -> &[u8] {
+        &self.mmap[self.bom_length..]
+    }
+}
+
+/// Maps the file at `path` into memory, so that it can be parsed without copying its text, unless
+/// it is compressed or not in UTF-8.
+///
+/// The file must not be changed while it is mapped.
+pub fn map_uncompressed(
+    path: &Path,
+    encoding: Option<&'static Encoding>,
+) -> Result<Option<MappedDocument>> {
+    let file = File::open(path)?;
+    // SAFETY: the file is only read, and changing it while it is mapped is documented as not
+    // allowed
+    let mmap = unsafe { Mmap::map(&file)? };
+    if Compression::detect(&mmap) != Compression::None {
+        return Ok(None);
+    }
+    let detected = DocumentEncoding::detect(&mmap, encoding)?;
+    if detected.encoding != UTF_8 {
+        return Ok(None);
+    }
+    Ok(Some(MappedDocument {
+        mmap,
+        bom_length: detected.bom_length,
+    }))
+}
@@ -0,0 +1,388 @@
+//! WFS 2.0 `GetFeature` requests, such as to PDOK's addresses service at
+//! <https://service.pdok.nl/kadaster/ad/wfs/v1_0>.
+//!
+//! A service returns at most a page of features per request, so the request is repeated for the
+//! next page until all features have been read, following the `next` link of each page if the
+//! service gives one, and counting with `STARTINDEX` otherwise. As a service may return fewer
+//! features than asked for, counting stops at the number of features the service says match, or
+//! else at an empty page.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{BufRead, BufReader, Read};
+use std::str::FromStr;
+
+use quick_xml::{Reader, events::Event};
+
+use crate::{
+    FeatureMember,
+    emitter::FeatureMemberEmitter,
+    error::{Error, Result},
+    parser::{Mode, Parser, ReferenceResolver, Selection},
+    source,
+};
+
+/// A `GetFeature` request for the features of one or more types, read page by page.
+pub struct GetFeature {
+    url: String,
+    type_names: Vec<String>,
+    count: u64,
+    start_index: u64,
+    constraint: Option<Constraint>,
+    srs_name: Option<String>,
+    mode: Mode,
+    selection: Selection,
+    skipped: BTreeMap<String, u64>,
+}
+
+/// Which features to request, besides their type.
+enum Constraint {
+    BoundingBox(BoundingBox),
+    /// An FES 2.0 `fes:Filter`.
+    Filter(String),
+}
+
+/// A bounding box to request the features in, written as `min_x,min_y,max_x,max_y` with an
+/// optional CRS, as in the `BBOX` parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundingBox {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+    /// E.g. `urn:ogc:def:crs:EPSG::28992`; the CRS of the service if not given.
+    pub crs: Option<String>,
+}
+
+impl FromStr for BoundingBox {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid bounding box: {}", s);
+        let mut parts = s.splitn(5, ',');
+        let mut coordinate = || -> Result<f64, String> {
+            let part = parts.next().ok_or_else(invalid)?;
+            part.trim().parse().map_err(|_| invalid())
+        };
+        let (min_x, min_y, max_x, max_y) =
+            (coordinate()?, coordinate()?, coordinate()?, coordinate()?);
+        Ok(BoundingBox {
+            min_x,
+            min_y,
+            max_x,
+            max_y,
+            crs: parts.next().map(|crs| crs.trim().to_string()),
+        })
+    }
+}
+
+impl fmt::Display for BoundingBox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},{}",
+            self.min_x, self.min_y, self.max_x, self.max_y
+        )?;
+        if let Some(crs) = &self.crs {
+            write!(f, ",{}", crs)?;
+        }
+        Ok(())
+    }
+}
+
+/// What the root element of a page says about the next one.
+#[derive(Default)]
+struct Page {
+    /// The URL of the next page.
+    next: Option<String>,
+    number_returned: Option<u64>,
+    /// The number of features of all pages together.
+    number_matched: Option<u64>,
+    exception_report: bool,
+}
+
+impl Page {
+    /// Reads the attributes of the root element at the start of `start`. A root element that
+    /// doesn't fit in `start` tells nothing.
+    fn from_start(start: &[u8]) -> Page {
+        let mut xml_reader = Reader::from_reader(start);
+        loop {
+            match xml_reader.read_event() {
+                Ok(Event::Start(e) | Event::Empty(e)) => {
+                    let mut page = Page {
+                        exception_report: e.local_name().as_ref() == b"ExceptionReport",
+                        ..Page::default()
+                    };
+                    for attr in e.attributes().flatten() {
+                        let Ok(value) = attr.unescape_value() else {
+                            continue;
+                        };
+                        match attr.key.local_name().as_ref() {
+                            b"next" => page.next = Some(value.into_owned()),
+                            // Which may also be `unknown`
+                            b"numberReturned" => page.number_returned = value.parse().ok(),
+                            b"numberMatched" => page.number_matched = value.parse().ok(),
+                            _ => {}
+                        }
+                    }
+                    return page;
+                }
+                Ok(Event::Eof) | Err(_) => return Page::default(),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl GetFeature {
+    /// Requests the features of `type_names`, e.g. `ad:Address`, from the service at `url`.
+    ///
+    /// Features of several types are requested with a query per type, so a page can hold
+    /// features of any of them.
+    pub fn new(
+        url: impl Into<String>,
+        type_names: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        GetFeature {
+            url: url.into(),
+            type_names: type_names.into_iter().map(Into::into).collect(),
+            count: 1000,
+            start_index: 0,
+            constraint: None,
+            srs_name: None,
+            mode: Mode::default(),
+            selection: Selection::all(),
+            skipped: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the number of features per page, which the service may lower to its own maximum.
+    pub fn with_count(mut self, count: u64) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// Starts at the feature with this index, counting from 0, instead of at the first.
+    pub fn with_start_index(mut self, start_index: u64) -> Self {
+        self.start_index = start_index;
+        self
+    }
+
+    /// Only requests features within `bbox`, instead of those matching a filter.
+    pub fn with_bbox(mut self, bbox: BoundingBox) -> Self {
+        self.constraint = Some(Constraint::BoundingBox(bbox));
+        self
+    }
+
+    /// Only requests features matching `filter`, an FES 2.0 `fes:Filter` element, instead of
+    /// those within a bounding box. The filter applies to every type.
+    pub fn with_filter(mut self, filter: impl Into<String>) -> Self {
+        self.constraint = Some(Constraint::Filter(filter.into()));
+        self
+    }
+
+    /// Requests positions in this CRS, e.g. `urn:ogc:def:crs:EPSG::28992`, instead of in the
+    /// CRS of the service.
+    pub fn with_srs_name(mut self, srs_name: impl Into<String>) -> Self {
+        self.srs_name = Some(srs_name.into());
+        self
+    }
+
+    pub fn with_mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// See [`Parser::with_selection`]. Feature members that are not selected still count
+    /// towards a full page.
+    pub fn with_selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// Reads every page, emitting its feature members in order, like [`Parser::parse`].
+    pub fn parse<T>(mut self, mut emitter: T) -> Result<T>
+    where
+        T: FeatureMemberEmitter,
+    {
+        emitter.start()?;
+        self.for_each(|feature_member| emitter.emit(feature_member))?;
+        emitter.end()?;
+        Ok(emitter)
+    }
+
+    /// Calls `f` with every feature member of every page, in order.
+    ///
+    /// Each page is parsed while it is downloaded. References to features on earlier pages are
+    /// resolved as if all pages were one document.
+    pub fn for_each<F>(&mut self, mut f: F) -> Result<()>
+    where
+        F: FnMut(FeatureMember<'static>) -> Result<()>,
+    {
+        let mut resolver = ReferenceResolver::default();
+        let mut start_index = self.start_index;
+        let mut request = Some(self.request(start_index));
+        // Whether the service links to the next page, so that a page without a link is the last
+        let mut linked = false;
+        while let Some(page_request) = request.take() {
+            let url = page_request.url().to_string();
+            let response = page_request.call().map_err(service_error)?;
+            let mut reader = source::decode(BufReader::new(response.into_reader()), None)?;
+            let page = Page::from_start(reader.fill_buf()?);
+            if page.exception_report {
+                let message = exception_text(reader)
+                    .unwrap_or_else(|| "an exception report without text".to_string());
+                return Err(Error::Service { url, message });
+            }
+
+            let mut feature_members = Parser::new(reader)
+                .with_mode(self.mode)
+                .with_selection(self.selection.clone())
+                .into_iter()
+                .with_resolver(resolver);
+            let mut read = 0;
+            for feature_member in &mut feature_members {
+                f(feature_member?)?;
+                read += 1;
+            }
+            resolver = feature_members.take_resolver();
+            read += feature_members.unselected();
+            for (tag, count) in feature_members.skipped() {
+                *self.skipped.entry(tag.clone()).or_default() += count;
+                read += count;
+            }
+
+            let returned = page.number_returned.unwrap_or(read);
+            start_index += returned;
+            // An empty page ends the response even if it links to another one
+            request = match page.next {
+                _ if returned == 0 => None,
+                Some(next) => {
+                    linked = true;
+                    Some(ureq::get(&next))
+                }
+                // A service that doesn't link its pages may return fewer features than the count
+                // on any page, so only the number of matching features tells the last one
+                None if !linked
+                    && page
+                        .number_matched
+                        .is_none_or(|number_matched| start_index < number_matched) =>
+                {
+                    Some(self.request(start_index))
+                }
+                None => None,
+            };
+        }
+        Ok(())
+    }
+
+    /// Returns how many feature members have been skipped in lenient mode, per tag in Clark
+    /// notation.
+    pub fn skipped(&self) -> &BTreeMap<String, u64> {
+        &self.skipped
+    }
+
+    /// Builds the request for the page starting at `start_index`.
+    fn request(&self, start_index: u64) -> ureq::Request {
+        // One query per type, as a comma-separated list would ask for joined tuples of features
+        let queries = |parameter: &str| {
+            if self.type_names.len() == 1 {
+                parameter.to_string()
+            } else {
+                format!("({})", parameter).repeat(self.type_names.len())
+            }
+        };
+        let type_names = if self.type_names.len() == 1 {
+            self.type_names[0].clone()
+        } else {
+            self.type_names
+                .iter()
+                .map(|type_name| format!("({})", type_name))
+                .collect()
+        };
+        let mut request = ureq::get(&self.url)
+            .query("SERVICE", "WFS")
+            .query("VERSION", "2.0.0")
+            .query("REQUEST", "GetFeature")
+            .query("TYPENAMES", &type_names)
+            .query("COUNT", &self.count.to_string())
+            .query("STARTINDEX", &start_index.to_string());
+        match &self.constraint {
+            Some(Constraint::BoundingBox(bbox)) => {
+                request = request.query("BBOX", &bbox.to_string())
+            }
+            Some(Constraint::Filter(filter)) => request = request.query("FILTER", &queries(filter)),
+            None => {}
+        }
+        if let Some(srs_name) = &self.srs_name {
+            request = request.query("SRSNAME", srs_name);
+        }
+        request
+    }
+}
+
+/// Turns an error status with an exception report into an [`Error::Service`], as services
+/// explain what is wrong with a request in one.
+fn service_error(error: ureq::Error) -> Error {
+    match error {
+        ureq::Error::Status(status, response) if response.content_type().contains("xml") => {
+            let url = response.get_url().to_string();
+            let status_text = response.status_text().to_string();
+            let message = exception_text(BufReader::new(response.into_reader().take(1 << 20)))
+                .unwrap_or_else(|| format!("{} {}", status, status_text));
+            Error::Service { url, message }
+        }
+        error => error.into(),
+    }
+}
+
+/// Returns the exception texts of an OWS exception report, or else its exception codes, or
+/// `None` if `reader` doesn't hold either.
+fn exception_text(reader: impl BufRead) -> Option<String> {
+    let mut xml_reader = Reader::from_reader(reader);
+    let mut buf = Vec::new();
+    let mut codes = Vec::new();
+    let mut texts: Vec<String> = Vec::new();
+    let mut in_text = false;
+    loop {
+        buf.clear();
+        match xml_reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) if e.local_name().as_ref() == b"ExceptionText" => {
+                texts.push(String::new());
+                in_text = true;
+            }
+            Ok(Event::Start(e) | Event::Empty(e)) if e.local_name().as_ref() == b"Exception" => {
+                codes.extend(
+                    e.attributes()
+                        .flatten()
+                        .filter(|attr| attr.key.local_name().as_ref() == b"exceptionCode")
+                        .filter_map(|attr| attr.unescape_value().ok())
+                        .map(|code| code.into_owned()),
+                );
+            }
+            Ok(Event::Text(e)) if in_text => {
+                if let (Some(text), Ok(unescaped)) = (texts.last_mut(), e.unescape()) {
+                    text.push_str(&unescaped);
+                }
+            }
+            Ok(Event::CData(e)) if in_text => {
+                if let Some(text) = texts.last_mut() {
+                    text.push_str(&String::from_utf8_lossy(&e));
+                }
+            }
+            Ok(Event::End(e)) if e.local_name().as_ref() == b"ExceptionText" => in_text = false,
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    let texts: Vec<&str> = texts
+        .iter()
+        .map(|text| text.trim())
+        .filter(|text| !text.is_empty())
+        .collect();
+    match (texts.is_empty(), codes.is_empty()) {
+        (false, _) => Some(texts.join("; ")),
+        (true, false) => Some(codes.join("; ")),
+        (true, true) => None,
+    }
+}
@@ -0,0 +1,230 @@
+mod common;
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use common::serve_files;
+use gmlparser::Error;
+use gmlparser::atom::{Feed, Link, fetch, read_feed};
+use sha2::{Digest, Sha256};
+
+const SERVICE_FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xmlns:inspire_dls="http://inspire.ec.europa.eu/schemas/inspire_dls/1.0" xml:lang="nl">
+  <id>https://service.pdok.nl/kadaster/ad/atom/index.xml</id>
+  <title>INSPIRE Download Service van Adressen</title>
+  <updated>2024-05-01T12:00:00Z</updated>
+  <entry>
+    <id>https://service.pdok.nl/kadaster/ad/atom/adressen.xml</id>
+    <title>Adressen</title>
+    <inspire_dls:spatial_dataset_identifier_code>4a2fc1e8-0b59-4a09-a3e4-f1bc7c2f4a7e</inspire_dls:spatial_dataset_identifier_code>
+    <inspire_dls:spatial_dataset_identifier_namespace>http://www.pdok.nl</inspire_dls:spatial_dataset_identifier_namespace>
+    <link rel="describedby" href="https://example.org/metadata.xml" type="application/xml"/>
+    <link rel="alternate" href="https://service.pdok.nl/kadaster/ad/atom/adressen.xml" type="application/atom+xml"/>
+    <updated>2024-05-01T12:00:00+02:00</updated>
+    <category term="http://www.opengis.net/def/crs/EPSG/0/28992" label="Amersfoort / RD New"/>
+  </entry>
+</feed>"#;
+
+fn dataset_feed(href: &str, length: usize) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<atom:feed xmlns:atom="http://www.w3.org/2005/Atom">
+  <atom:title><![CDATA[Adressen]]></atom:title>
+  <atom:entry>
+    <atom:title>Adressen &amp; postcodes (GML)</atom:title>
+    <atom:link rel="alternate" href="{}" type="application/zip" length="{}" title="Download"/>
+    <atom:updated>2024-04-30</atom:updated>
+    <atom:category term="http://www.opengis.net/def/crs/EPSG/0/28992" label="Amersfoort / RD New"/>
+  </atom:entry>
+</atom:feed>"#,
+        href, length
+    )
+}
+
+const ARCHIVE: &[u8] = b"not really a zip archive, but bytes all the same";
+
+fn archive_sha256() -> String {
+    format!("{:x}", Sha256::digest(ARCHIVE))
+}
+
+fn cache_dir(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("gmlparser-atom-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&path);
+    path
+}
+
+fn archive_link(base_url: &str) -> Link {
+    Link {
+        href: format!("{}/downloads/adressen.zip", base_url),
+        rel: Some("alternate".to_string()),
+        media_type: Some("application/zip".to_string()),
+        length: Some(ARCHIVE.len() as u64),
+        title: None,
+    }
+}
+
+#[test]
+fn parses_service_feed() {
+    let feed = Feed::from_reader(SERVICE_FEED.as_bytes()).unwrap();
+    assert_eq!(
+        feed.title.as_deref(),
+        Some("INSPIRE Download Service van Adressen")
+    );
+    assert_eq!(feed.updated.unwrap().to_string(), "2024-05-01 12:00:00");
+
+    let [entry] = feed.entries.as_slice() else {
+        panic!("expected one entry");
+    };
+    assert_eq!(entry.title.as_deref(), Some("Adressen"));
+    assert_eq!(
+        entry.dataset_identifier.as_deref(),
+        Some("4a2fc1e8-0b59-4a09-a3e4-f1bc7c2f4a7e")
+    );
+    // Converted to UTC
+    assert_eq!(entry.updated.unwrap().to_string(), "2024-05-01 10:00:00");
+    assert_eq!(
+        entry.dataset_feed().map(|link| link.href.as_str()),
+        Some("https://service.pdok.nl/kadaster/ad/atom/adressen.xml")
+    );
+    assert_eq!(entry.downloads().count(), 0);
+    assert_eq!(entry.crs[0].label.as_deref(), Some("Amersfoort / RD New"));
+}
+
+#[test]
+fn parses_dataset_feed() {
+    let feed =
+        Feed::from_reader(dataset_feed("https://example.org/a.zip?x=1&amp;y=2", 42).as_bytes())
+            .unwrap();
+    assert_eq!(feed.title.as_deref(), Some("Adressen"));
+
+    let entry = &feed.entries[0];
+    assert_eq!(entry.title.as_deref(), Some("Adressen & postcodes (GML)"));
+    assert_eq!(entry.updated.unwrap().to_string(), "2024-04-30 00:00:00");
+    assert_eq!(
+        entry.crs[0].uri,
+        "http://www.opengis.net/def/crs/EPSG/0/28992"
+    );
+    assert!(entry.dataset_feed().is_none());
+
+    let downloads: Vec<_> = entry.downloads().collect();
+    assert_eq!(downloads.len(), 1);
+    assert_eq!(downloads[0].href, "https://example.org/a.zip?x=1&y=2");
+    assert_eq!(downloads[0].media_type.as_deref(), Some("application/zip"));
+    assert_eq!(downloads[0].length, Some(42));
+}
+
+#[test]
+fn reads_feed_and_fetches_download_over_http() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let base_url = serve_files(
+        vec![
+            (
+                "/atom/adressen.xml",
+                dataset_feed("../downloads/adressen.zip", ARCHIVE.len()).into_bytes(),
+            ),
+            ("/downloads/adressen.zip", ARCHIVE.to_vec()),
+        ],
+        requests.clone(),
+    );
+
+    let feed = read_feed(&format!("{}/atom/adressen.xml", base_url)).unwrap();
+    let link = feed.entries[0].downloads().next().unwrap();
+    // Resolved against the URL of the feed
+    assert_eq!(link.href, format!("{}/downloads/adressen.zip", base_url));
+    assert_eq!(link.length, Some(ARCHIVE.len() as u64));
+
+    let cache_dir = cache_dir("fetch");
+    let path = fetch(link, &cache_dir, Some(&archive_sha256())).unwrap();
+    assert_eq!(path.parent(), Some(cache_dir.as_path()));
+    let file_name = path.file_name().unwrap().to_str().unwrap();
+    assert!(file_name.ends_with("-adressen.zip"), "{}", file_name);
+    assert_eq!(std::fs::read(&path).unwrap(), ARCHIVE);
+    assert_eq!(
+        std::fs::read_to_string(cache_dir.join(format!("{}.sha256", file_name))).unwrap(),
+        format!("{}\n", archive_sha256())
+    );
+    assert_eq!(requests.lock().unwrap().len(), 2);
+
+    // A verified file in the cache is not downloaded again
+    fetch(link, &cache_dir, None).unwrap();
+    assert_eq!(requests.lock().unwrap().len(), 2);
+
+    // But a corrupted one is
+    std::fs::write(&path, b"corrupted").unwrap();
+    fetch(link, &cache_dir, None).unwrap();
+    assert_eq!(requests.lock().unwrap().len(), 3);
+    assert_eq!(std::fs::read(&path).unwrap(), ARCHIVE);
+}
+
+#[test]
+fn rejects_download_with_wrong_checksum() {
+    let base_url = serve_files(
+        vec![("/downloads/adressen.zip", ARCHIVE.to_vec())],
+        Arc::default(),
+    );
+    let cache_dir = cache_dir("checksum");
+    let wrong_checksum = "0".repeat(64);
+
+    let error = fetch(&archive_link(&base_url), &cache_dir, Some(&wrong_checksum)).unwrap_err();
+    assert!(
+        matches!(&error, Error::Verification { found, .. } if found.contains(&archive_sha256())),
+        "{}",
+        error
+    );
+    // Nothing is left behind in the cache
+    assert_eq!(std::fs::read_dir(&cache_dir).unwrap().count(), 0);
+}
+
+#[test]
+fn rejects_download_with_wrong_length() {
+    let base_url = serve_files(
+        vec![("/downloads/adressen.zip", ARCHIVE[..10].to_vec())],
+        Arc::default(),
+    );
+    let cache_dir = cache_dir("length");
+
+    let error = fetch(&archive_link(&base_url), &cache_dir, None).unwrap_err();
+    assert!(
+        matches!(&error, Error::Verification { found, .. } if found == "10 bytes"),
+        "{}",
+        error
+    );
+    assert_eq!(std::fs::read_dir(&cache_dir).unwrap().count(), 0);
+}
+
+#[test]
+fn caches_downloads_with_the_same_file_name_apart() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let other_archive = b"another archive".to_vec();
+    let base_url = serve_files(
+        vec![
+            ("/downloads/adressen.zip", ARCHIVE.to_vec()),
+            ("/2023/downloads/adressen.zip", other_archive.clone()),
+        ],
+        requests.clone(),
+    );
+    let cache_dir = cache_dir("names");
+    let other_link = Link {
+        href: format!("{}/2023/downloads/adressen.zip", base_url),
+        length: None,
+        ..archive_link(&base_url)
+    };
+
+    let path = fetch(&archive_link(&base_url), &cache_dir, None).unwrap();
+    let other_path = fetch(&other_link, &cache_dir, None).unwrap();
+    assert_ne!(path, other_path);
+    assert_eq!(std::fs::read(&path).unwrap(), ARCHIVE);
+    assert_eq!(std::fs::read(&other_path).unwrap(), other_archive);
+
+    // Both stay in the cache
+    fetch(&archive_link(&base_url), &cache_dir, None).unwrap();
+    fetch(&other_link, &cache_dir, None).unwrap();
+    assert_eq!(requests.lock().unwrap().len(), 2);
+}
+
+#[test]
+fn reports_missing_download() {
+    let base_url = serve_files(Vec::new(), Arc::default());
+    let error = fetch(&archive_link(&base_url), &cache_dir("missing"), None).unwrap_err();
+    assert!(matches!(error, Error::Http(_)), "{}", error);
+}
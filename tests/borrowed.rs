@@ -0,0 +1,73 @@
+mod common;
+
+use std::borrow::Cow;
+
+use common::{address, describe, document, fixture, inspire_id, thoroughfare};
+use gmlparser::{FeatureMember, Mode, Parser};
+
+fn owned(document: &str, mode: Mode) -> Vec<String> {
+    Parser::new(document.as_bytes())
+        .with_mode(mode)
+        .into_iter()
+        .map(|feature_member| describe(&feature_member.unwrap()))
+        .collect()
+}
+
+fn borrowed(document: &str, mode: Mode) -> Vec<String> {
+    Parser::new(document.as_bytes())
+        .with_mode(mode)
+        .into_borrowed_iter()
+        .map(|feature_member| describe(&feature_member.unwrap()))
+        .collect()
+}
+
+#[test]
+fn parses_the_fixture_like_the_owned_parser() {
+    let document = fixture("bag.gml");
+    let expected = owned(&document, Mode::Strict);
+    assert_eq!(expected.len(), 6);
+    assert_eq!(borrowed(&document, Mode::Strict), expected);
+}
+
+#[test]
+fn parses_escaped_and_split_text_like_the_owned_parser() {
+    let document = document(&[
+        thoroughfare("t1", "1", "Koning &amp; Co"),
+        thoroughfare("t2", "2", "<![CDATA[Koning & Co]]>"),
+        thoroughfare("t3", "3", "Koning<!-- & --> &amp; Co"),
+        "<ad:Foo/>".to_string(),
+        address("0001", &["#t1", "#t4"]),
+    ]);
+    let expected = owned(&document, Mode::Lenient);
+    assert_eq!(expected.len(), 4);
+    assert_eq!(borrowed(&document, Mode::Lenient), expected);
+}
+
+#[test]
+fn borrows_text_from_the_document() {
+    let document = document(&[
+        thoroughfare("t1", "1", "Dijkstraat"),
+        thoroughfare("t2", "2", "Koning &amp; Co"),
+        format!(
+            r#"<ad:ThoroughfareName gml:id="t3">{}</ad:ThoroughfareName>"#,
+            inspire_id("3&amp;4", "NL.IMBAG.OpenbareRuimte")
+        ),
+    ]);
+    let feature_members: Vec<_> = Parser::new(document.as_bytes())
+        .into_borrowed_iter()
+        .collect::<gmlparser::Result<_>>()
+        .unwrap();
+    let names: Vec<_> = feature_members
+        .iter()
+        .map(|feature_member| match feature_member {
+            FeatureMember::ThoroughfareName { local_id, name, .. } => (local_id, name),
+            _ => panic!("expected a thoroughfare"),
+        })
+        .collect();
+
+    assert!(matches!(names[0].0, Cow::Borrowed("1")));
+    assert!(matches!(names[0].1, Some(Cow::Borrowed("Dijkstraat"))));
+    // Escaped text has to be copied
+    assert!(matches!(names[1].1, Some(Cow::Owned(name)) if name == "Koning & Co"));
+    assert!(matches!(names[2].0, Cow::Owned(local_id) if local_id == "3&4"));
+}
@@ -0,0 +1,382 @@
+//! Helpers shared by the integration tests: small GML documents and fixtures, and a stand-in HTTP
+//! server.
+
+// Each test crate only uses some of the helpers
+#![allow(dead_code)]
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use gmlparser::{FeatureMember, GeographicalName, Identifier, Lifecycle, Parser, Reference};
+
+pub const NAMESPACES: &str = r#"xmlns:wfs="http://www.opengis.net/wfs/2.0" xmlns:gml="http://www.opengis.net/gml/3.2" xmlns:ad="http://inspire.ec.europa.eu/schemas/ad/4.0" xmlns:base="http://inspire.ec.europa.eu/schemas/base/3.3" xmlns:gn="http://inspire.ec.europa.eu/schemas/gn/4.0" xmlns:xlink="http://www.w3.org/1999/xlink""#;
+
+/// A `base:Identifier` in the `inspireId` of a feature.
+pub fn inspire_id(local_id: &str, namespace: &str) -> String {
+    format!(
+        "<ad:inspireId><base:Identifier><base:localId>{}</base:localId><base:namespace>{}</base:namespace></base:Identifier></ad:inspireId>",
+        local_id, namespace
+    )
+}
+
+/// An `ad:name` with a single spelling, as names of admin units, address areas and
+/// thoroughfares have.
+pub fn name(text: &str) -> String {
+    format!(
+        "<ad:name><gn:GeographicalName><gn:spelling><gn:SpellingOfName><gn:text>{}</gn:text></gn:SpellingOfName></gn:spelling></gn:GeographicalName></ad:name>",
+        text
+    )
+}
+
+pub fn admin_unit(gml_id: &str, local_id: &str, text: &str) -> String {
+    format!(
+        r#"<ad:AdminUnitName gml:id="{}">{}{}</ad:AdminUnitName>"#,
+        gml_id,
+        inspire_id(local_id, "NL.IMBAG.Gemeente"),
+        name(text)
+    )
+}
+
+pub fn thoroughfare(gml_id: &str, local_id: &str, text: &str) -> String {
+    format!(
+        r#"<ad:ThoroughfareName gml:id="{}">{}<ad:name><ad:ThoroughfareNameValue>{}</ad:ThoroughfareNameValue></ad:name></ad:ThoroughfareName>"#,
+        gml_id,
+        inspire_id(local_id, "NL.IMBAG.OpenbareRuimte"),
+        name(text)
+    )
+}
+
+/// An address with a component per href.
+pub fn address(local_id: &str, component_hrefs: &[&str]) -> String {
+    let components: String = component_hrefs
+        .iter()
+        .map(|href| format!(r#"<ad:component xlink:href="{}"/>"#, href))
+        .collect();
+    format!(
+        r#"<ad:Address gml:id="a{0}">{1}{2}</ad:Address>"#,
+        local_id,
+        inspire_id(local_id, "NL.IMBAG.Nummeraanduiding"),
+        components
+    )
+}
+
+/// A `gml:FeatureCollection` with a `gml:featureMember` per feature.
+pub fn document(features: &[String]) -> String {
+    let members: String = features
+        .iter()
+        .map(|feature| format!("\n  <gml:featureMember>{}</gml:featureMember>", feature))
+        .collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<gml:FeatureCollection {}>{}\n</gml:FeatureCollection>\n",
+        NAMESPACES, members
+    )
+}
+
+/// Reads a document from `tests/fixtures`.
+pub fn fixture(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    std::fs::read_to_string(path).unwrap()
+}
+
+/// Parses `document` with the default settings.
+pub fn parse(document: &str) -> Vec<FeatureMember<'static>> {
+    Parser::new(document.as_bytes())
+        .into_iter()
+        .collect::<gmlparser::Result<_>>()
+        .unwrap()
+}
+
+pub fn local_id(feature_member: &FeatureMember<'_>) -> String {
+    match feature_member {
+        FeatureMember::Address { local_id, .. }
+        | FeatureMember::AdminUnitName { local_id, .. }
+        | FeatureMember::AddressAreaName { local_id, .. }
+        | FeatureMember::ThoroughfareName { local_id, .. }
+        | FeatureMember::PostalDescriptor { local_id, .. } => local_id.to_string(),
+    }
+}
+
+/// Returns an empty directory for a test, removing what an earlier run left in it.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("gmlparser-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(&path).unwrap();
+    path
+}
+
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn ok(content_type: &'static str, body: impl Into<Vec<u8>>) -> Response {
+        Response {
+            status: 200,
+            content_type,
+            body: body.into(),
+        }
+    }
+
+    pub fn not_found() -> Response {
+        Response {
+            status: 404,
+            content_type: "text/plain",
+            body: Vec::new(),
+        }
+    }
+}
+
+/// Answers every request with `respond(base_url, target)` over HTTP on a local port, recording
+/// the request targets. Returns the base URL.
+pub fn serve<F>(respond: F, requests: Arc<Mutex<Vec<String>>>) -> String
+where
+    F: Fn(&str, &str) -> Response + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let server_base_url = base_url.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+            }
+
+            let target = request_line.split(' ').nth(1).unwrap_or_default();
+            requests.lock().unwrap().push(target.to_string());
+            let response = respond(&server_base_url, target);
+            let reason = match response.status {
+                200 => "OK",
+                404 => "Not Found",
+                _ => "Bad Request",
+            };
+            write!(
+                stream,
+                "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                response.status,
+                reason,
+                response.content_type,
+                response.body.len(),
+            )
+            .unwrap();
+            stream.write_all(&response.body).unwrap();
+        }
+    });
+    base_url
+}
+
+/// Serves `files` by path, like [`serve`].
+pub fn serve_files(
+    files: Vec<(&'static str, Vec<u8>)>,
+    requests: Arc<Mutex<Vec<String>>>,
+) -> String {
+    serve(
+        move |_, target| match files.iter().find(|(path, _)| *path == target) {
+            Some((_, body)) => Response::ok("application/octet-stream", body.clone()),
+            None => Response::not_found(),
+        },
+        requests,
+    )
+}
+
+/// Writes out every field of a feature member, to compare the results of different ways of
+/// parsing a document.
+pub fn describe(feature_member: &FeatureMember<'_>) -> String {
+    fn reference(reference: &Option<Reference>) -> String {
+        references(reference.as_slice())
+    }
+    fn references(references: &[Reference]) -> String {
+        references
+            .iter()
+            .map(|reference| format!("{}={}", reference.href, reference.local_id))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+    fn names(names: &[GeographicalName<'_>]) -> String {
+        names
+            .iter()
+            .map(|name| {
+                let spellings: Vec<_> = name
+                    .spellings
+                    .iter()
+                    .map(|spelling| format!("{}/{:?}", spelling.text, spelling.script))
+                    .collect();
+                format!(
+                    "{:?}/{:?}/[{}]",
+                    name.language,
+                    name.name_status,
+                    spellings.join(",")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+    fn lifecycle(lifecycle: &Lifecycle) -> String {
+        format!(
+            "{:?} {:?} {:?} {:?} {:?}",
+            lifecycle.status,
+            lifecycle.valid_from,
+            lifecycle.valid_to,
+            lifecycle.begin_lifespan_version,
+            lifecycle.end_lifespan_version
+        )
+    }
+    fn identifier(identifier: &Identifier<'_>) -> String {
+        format!(
+            "{:?} {:?} {:?}",
+            identifier.version_id, identifier.namespace, identifier.gml_id
+        )
+    }
+
+    match feature_member {
+        FeatureMember::Address {
+            local_id,
+            number,
+            number_extension,
+            number_2nd_extension,
+            postal_delivery_identifier,
+            admin_unit_ref,
+            address_area_ref,
+            thoroughfare_ref,
+            postal_descriptor_ref,
+            other_component_refs,
+            parent_address_ref,
+            building_refs,
+            locators,
+            position,
+            lifecycle: address_lifecycle,
+            identifier: address_identifier,
+        } => {
+            let locators: Vec<_> = locators
+                .iter()
+                .map(|locator| {
+                    let designators: Vec<_> = locator
+                        .designators
+                        .iter()
+                        .map(|designator| {
+                            format!(
+                                "{:?}={:?}",
+                                designator.designator_type, designator.designator
+                            )
+                        })
+                        .collect();
+                    let locator_names: Vec<_> = locator
+                        .names
+                        .iter()
+                        .map(|name| format!("{:?}={}", name.name_type, names(&name.names)))
+                        .collect();
+                    format!(
+                        "{:?}[{}][{}]",
+                        locator.level,
+                        designators.join(","),
+                        locator_names.join(",")
+                    )
+                })
+                .collect();
+            let position = position.as_ref().map(|position| {
+                format!(
+                    "{} {} {:?} {:?} {}",
+                    position.x,
+                    position.y,
+                    position.method,
+                    position.specification,
+                    position.default
+                )
+            });
+            format!(
+                "Address {} {:?} {:?} {:?} {:?} [{}] [{}] [{}] [{}] [{}] [{}] [{}] [{}] {:?} {} {}",
+                local_id,
+                number,
+                number_extension,
+                number_2nd_extension,
+                postal_delivery_identifier,
+                reference(admin_unit_ref),
+                reference(address_area_ref),
+                reference(thoroughfare_ref),
+                reference(postal_descriptor_ref),
+                references(other_component_refs),
+                reference(parent_address_ref),
+                references(building_refs),
+                locators.join(";"),
+                position,
+                lifecycle(address_lifecycle),
+                identifier(address_identifier)
+            )
+        }
+        FeatureMember::AdminUnitName {
+            local_id,
+            name,
+            names: all_names,
+            lifecycle: name_lifecycle,
+            identifier: name_identifier,
+        } => format!(
+            "AdminUnitName {} {:?} [{}] {} {}",
+            local_id,
+            name,
+            names(all_names),
+            lifecycle(name_lifecycle),
+            identifier(name_identifier)
+        ),
+        FeatureMember::AddressAreaName {
+            local_id,
+            name,
+            names: all_names,
+            situated_in_ref,
+            lifecycle: name_lifecycle,
+            identifier: name_identifier,
+        } => format!(
+            "AddressAreaName {} {:?} [{}] [{}] {} {}",
+            local_id,
+            name,
+            names(all_names),
+            reference(situated_in_ref),
+            lifecycle(name_lifecycle),
+            identifier(name_identifier)
+        ),
+        FeatureMember::ThoroughfareName {
+            local_id,
+            name,
+            names: all_names,
+            situated_in_ref,
+            lifecycle: name_lifecycle,
+            identifier: name_identifier,
+        } => format!(
+            "ThoroughfareName {} {:?} [{}] [{}] {} {}",
+            local_id,
+            name,
+            names(all_names),
+            reference(situated_in_ref),
+            lifecycle(name_lifecycle),
+            identifier(name_identifier)
+        ),
+        FeatureMember::PostalDescriptor {
+            local_id,
+            post_code,
+            post_name,
+            admin_unit_ref,
+            lifecycle: descriptor_lifecycle,
+            identifier: descriptor_identifier,
+        } => format!(
+            "PostalDescriptor {} {:?} {:?} [{}] {} {}",
+            local_id,
+            post_code,
+            post_name,
+            reference(admin_unit_ref),
+            lifecycle(descriptor_lifecycle),
+            identifier(descriptor_identifier)
+        ),
+    }
+}
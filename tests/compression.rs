@@ -0,0 +1,114 @@
+mod common;
+
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use common::{address, describe, document, fixture, parse, temp_dir, thoroughfare};
+use gmlparser::Parser;
+use gmlparser::source::{Compression, for_each_document};
+
+/// Returns the name and the described feature members of every document in the file at `path`.
+fn documents(path: &Path) -> Vec<(Option<String>, Vec<String>)> {
+    let mut documents = Vec::new();
+    for_each_document(path, None, |name, reader: &mut dyn BufRead| {
+        let descriptions = Parser::new(reader)
+            .into_iter()
+            .map(|feature_member| feature_member.map(|feature_member| describe(&feature_member)))
+            .collect::<gmlparser::Result<_>>()?;
+        documents.push((name.map(str::to_string), descriptions));
+        Ok(())
+    })
+    .unwrap();
+    documents
+}
+
+fn descriptions(document: &str) -> Vec<String> {
+    parse(document).iter().map(describe).collect()
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn detects_compression_by_magic_bytes() {
+    let document = fixture("bag.gml");
+    assert_eq!(Compression::detect(document.as_bytes()), Compression::None);
+    assert_eq!(
+        Compression::detect(&gzip(document.as_bytes())),
+        Compression::Gzip
+    );
+    assert_eq!(
+        Compression::detect(&zstd::encode_all(document.as_bytes(), 0).unwrap()),
+        Compression::Zstd
+    );
+    assert_eq!(Compression::detect(b"PK\x03\x04"), Compression::Zip);
+    // An empty zip archive
+    assert_eq!(Compression::detect(b"PK\x05\x06"), Compression::Zip);
+    assert_eq!(Compression::detect(b""), Compression::None);
+}
+
+#[test]
+fn reads_gzip_and_zstd_compressed_documents() {
+    let dir = temp_dir("compression");
+    let document = fixture("bag.gml");
+    let expected = vec![(None, descriptions(&document))];
+
+    let plain_path = dir.join("bag.gml");
+    std::fs::write(&plain_path, &document).unwrap();
+    assert_eq!(documents(&plain_path), expected);
+
+    let gzip_path = dir.join("bag.gml.gz");
+    std::fs::write(&gzip_path, gzip(document.as_bytes())).unwrap();
+    assert_eq!(documents(&gzip_path), expected);
+
+    // A file of several gzip members is read as one document
+    let (start, end) = document.split_at(document.len() / 2);
+    let mut members = gzip(start.as_bytes());
+    members.extend(gzip(end.as_bytes()));
+    std::fs::write(&gzip_path, members).unwrap();
+    assert_eq!(documents(&gzip_path), expected);
+
+    let zstd_path = dir.join("bag.gml.zst");
+    std::fs::write(
+        &zstd_path,
+        zstd::encode_all(document.as_bytes(), 3).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(documents(&zstd_path), expected);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn reads_every_gml_document_in_a_zip_archive() {
+    let dir = temp_dir("zip");
+    let zip_path = dir.join("adressen.zip");
+    let streets = document(&[thoroughfare("t1", "0003300000116985", "Dijkstraat")]);
+    let addresses = document(&[address("0001", &["#t1"])]);
+
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+    let options = zip::write::SimpleFileOptions::default();
+    writer.start_file("README.txt", options).unwrap();
+    writer.write_all(b"Not a GML document").unwrap();
+    writer.add_directory("gml/", options).unwrap();
+    writer.start_file("gml/streets.gml", options).unwrap();
+    writer.write_all(streets.as_bytes()).unwrap();
+    writer.start_file("gml/ADDRESSES.GML", options).unwrap();
+    writer.write_all(addresses.as_bytes()).unwrap();
+    writer.finish().unwrap();
+
+    assert_eq!(
+        documents(&zip_path),
+        [
+            (Some("gml/streets.gml".to_string()), descriptions(&streets)),
+            (
+                Some("gml/ADDRESSES.GML".to_string()),
+                descriptions(&addresses)
+            ),
+        ]
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use std::num::NonZeroUsize;
+
+use common::{NAMESPACES, address, admin_unit, local_id, parse, thoroughfare};
+use gmlparser::ParallelParser;
+
+const BOUNDED_BY: &str = r#"<gml:boundedBy><gml:Envelope srsName="urn:ogc:def:crs:EPSG::28992"><gml:lowerCorner>0 0</gml:lowerCorner><gml:upperCorner>1 1</gml:upperCorner></gml:Envelope></gml:boundedBy>"#;
+
+fn local_ids(document: &str) -> Vec<String> {
+    parse(document).iter().map(local_id).collect()
+}
+
+#[test]
+fn reads_feature_members() {
+    let document = format!(
+        "<gml:FeatureCollection {}>{}<gml:featureMembers>{}{}</gml:featureMembers></gml:FeatureCollection>",
+        NAMESPACES,
+        BOUNDED_BY,
+        thoroughfare("t1", "0003300000116985", "Dijkstraat"),
+        address("0001", &["#t1"]),
+    );
+    assert_eq!(local_ids(&document), ["0003300000116985", "0001"]);
+}
+
+#[test]
+fn reads_wfs_members_and_nested_collections() {
+    let document = format!(
+        concat!(
+            r#"<wfs:FeatureCollection {0} numberMatched="3" numberReturned="2">"#,
+            r#"<wfs:boundedBy><gml:Envelope/></wfs:boundedBy>{1}"#,
+            "<wfs:member>{2}</wfs:member>",
+            "<wfs:member><wfs:FeatureCollection>{1}<wfs:member>{3}</wfs:member></wfs:FeatureCollection></wfs:member>",
+            "<wfs:additionalObjects><wfs:SimpleFeatureCollection><wfs:member>{4}</wfs:member></wfs:SimpleFeatureCollection></wfs:additionalObjects>",
+            "</wfs:FeatureCollection>"
+        ),
+        NAMESPACES,
+        BOUNDED_BY,
+        thoroughfare("t1", "0003300000116985", "Dijkstraat"),
+        address("0001", &["#t1"]),
+        admin_unit("g1", "0363", "Amsterdam"),
+    );
+    assert_eq!(local_ids(&document), ["0003300000116985", "0001", "0363"]);
+}
+
+#[test]
+fn skips_other_properties_of_collections() {
+    let document = format!(
+        concat!(
+            r#"<gml:FeatureCollection {0} gml:id="c1">"#,
+            "<gml:metaDataProperty><gml:GenericMetaData>BAG</gml:GenericMetaData></gml:metaDataProperty>",
+            "<gml:description>Adressen</gml:description>",
+            r#"<gml:identifier codeSpace="http://example.com">c1</gml:identifier>"#,
+            "<gml:name>BAG</gml:name>{1}",
+            "<gml:featureMember>{2}</gml:featureMember>",
+            "<gml:description>Between members</gml:description>",
+            "<gml:featureMember>{3}</gml:featureMember>",
+            "</gml:FeatureCollection>"
+        ),
+        NAMESPACES,
+        BOUNDED_BY,
+        thoroughfare("t1", "0003300000116985", "Dijkstraat"),
+        address("0001", &["#t1"]),
+    );
+    assert_eq!(local_ids(&document), ["0003300000116985", "0001"]);
+
+    for chunk_size in [1, 1 << 20] {
+        let mut local_ids = Vec::new();
+        ParallelParser::new(document.as_bytes())
+            .with_threads(NonZeroUsize::new(2).unwrap())
+            .with_chunk_size(chunk_size)
+            .for_each(|feature_member| {
+                local_ids.push(local_id(&feature_member));
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(local_ids, ["0003300000116985", "0001"]);
+    }
+}
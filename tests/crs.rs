@@ -0,0 +1,71 @@
+use gmlparser::crs::{rd_to_wgs84, wgs84_to_rd};
+
+/// The Onze Lieve Vrouwetoren in Amersfoort, the origin of the RD system.
+const AMERSFOORT_RD: (f64, f64) = (155_000.0, 463_000.0);
+const AMERSFOORT_WGS84: (f64, f64) = (52.155_174_40, 5.387_206_21);
+
+/// The Westertoren in Amsterdam as the approximation itself transforms it: a worked example of the
+/// polynomials away from the origin, which catches changes to their coefficients. It says nothing
+/// about how close the approximation comes to RDNAPTRANS.
+const WESTERTOREN_RD: (f64, f64) = (120_700.723, 487_525.501);
+const WESTERTOREN_WGS84: (f64, f64) = (52.374_532_53, 4.883_525_59);
+
+/// Roughly one metre, expressed in degrees of latitude.
+const METRE_IN_DEGREES: f64 = 1.0 / 111_000.0;
+
+#[test]
+fn amersfoort_rd_to_wgs84() {
+    let (lat, lon) = rd_to_wgs84(AMERSFOORT_RD.0, AMERSFOORT_RD.1);
+    assert!((lat - AMERSFOORT_WGS84.0).abs() < 1e-9, "lat {}", lat);
+    assert!((lon - AMERSFOORT_WGS84.1).abs() < 1e-9, "lon {}", lon);
+}
+
+#[test]
+fn amersfoort_wgs84_to_rd() {
+    let (x, y) = wgs84_to_rd(AMERSFOORT_WGS84.0, AMERSFOORT_WGS84.1);
+    assert!((x - AMERSFOORT_RD.0).abs() < 1e-6, "x {}", x);
+    assert!((y - AMERSFOORT_RD.1).abs() < 1e-6, "y {}", y);
+}
+
+#[test]
+fn westertoren_rd_to_wgs84() {
+    // The worked example has 8 decimals, about a millimetre
+    let (lat, lon) = rd_to_wgs84(WESTERTOREN_RD.0, WESTERTOREN_RD.1);
+    assert!((lat - WESTERTOREN_WGS84.0).abs() < 1e-8, "lat {}", lat);
+    assert!((lon - WESTERTOREN_WGS84.1).abs() < 1e-8, "lon {}", lon);
+}
+
+#[test]
+fn westertoren_wgs84_to_rd() {
+    let (x, y) = wgs84_to_rd(WESTERTOREN_WGS84.0, WESTERTOREN_WGS84.1);
+    assert!((x - WESTERTOREN_RD.0).abs() < 0.001, "x {}", x);
+    assert!((y - WESTERTOREN_RD.1).abs() < 0.001, "y {}", y);
+}
+
+#[test]
+fn round_trip_within_a_metre() {
+    // Corners and centre of the area covered by RD New in the Netherlands
+    let points = [
+        (13_000.0, 370_000.0),
+        (13_000.0, 620_000.0),
+        (280_000.0, 300_000.0),
+        (280_000.0, 620_000.0),
+        (121_000.0, 487_000.0),
+        (253_611.0, 593_125.0),
+    ];
+    for (x, y) in points {
+        let (lat, lon) = rd_to_wgs84(x, y);
+        let (x2, y2) = wgs84_to_rd(lat, lon);
+        assert!((x - x2).abs() < 1.0, "x {} -> {}", x, x2);
+        assert!((y - y2).abs() < 1.0, "y {} -> {}", y, y2);
+    }
+}
+
+#[test]
+fn one_kilometre_north_of_amersfoort() {
+    // Going north along the central meridian of RD only changes the latitude, by about 1 km
+    let (lat, lon) = rd_to_wgs84(AMERSFOORT_RD.0, AMERSFOORT_RD.1 + 1000.0);
+    assert!((lon - AMERSFOORT_WGS84.1).abs() < METRE_IN_DEGREES);
+    let metres = (lat - AMERSFOORT_WGS84.0) / METRE_IN_DEGREES;
+    assert!((metres - 1000.0).abs() < 10.0, "moved {} m", metres);
+}
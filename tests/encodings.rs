@@ -0,0 +1,106 @@
+mod common;
+
+use common::{describe, fixture, parse};
+use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE, WINDOWS_1252};
+use gmlparser::source::{DocumentEncoding, decode};
+use gmlparser::{Error, FeatureMember, Parser};
+
+/// The fixture with a street name that isn't ASCII, declared to be in `label`.
+fn document(label: &str) -> String {
+    fixture("bag.gml")
+        .replacen("UTF-8", label, 1)
+        .replace("Oldehoofsterkerkhof", "Âldehôfster Tsjerkhôf")
+}
+
+fn encode(document: &str, encoding: &'static Encoding) -> Vec<u8> {
+    encoding.encode(document).0.into_owned()
+}
+
+fn utf16(document: &str, big_endian: bool) -> Vec<u8> {
+    document
+        .encode_utf16()
+        .flat_map(|unit| {
+            if big_endian {
+                unit.to_be_bytes()
+            } else {
+                unit.to_le_bytes()
+            }
+        })
+        .collect()
+}
+
+fn descriptions(bytes: &[u8], encoding: Option<&'static Encoding>) -> Vec<String> {
+    Parser::new(decode(bytes, encoding).unwrap())
+        .into_iter()
+        .map(|feature_member| describe(&feature_member.unwrap()))
+        .collect()
+}
+
+fn expected() -> Vec<String> {
+    parse(&document("UTF-8")).iter().map(describe).collect()
+}
+
+#[test]
+fn detects_the_encoding_from_the_bom_or_declaration() {
+    let detect = |start: &[u8]| DocumentEncoding::detect(start, None).unwrap();
+    assert_eq!(detect(b"<?xml version=\"1.0\"?>").encoding, UTF_8);
+    assert_eq!(
+        detect(document("ISO-8859-1").as_bytes()).encoding,
+        WINDOWS_1252
+    );
+    assert_eq!(
+        detect(b"\xEF\xBB\xBF<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?>"),
+        DocumentEncoding {
+            encoding: UTF_8,
+            bom_length: 3
+        }
+    );
+    assert_eq!(
+        detect(&utf16("\u{FEFF}<?xml", true)),
+        DocumentEncoding {
+            encoding: UTF_16BE,
+            bom_length: 2
+        }
+    );
+    // Without a byte-order mark, by how `<?` is encoded
+    assert_eq!(detect(&utf16("<?xml", false)).encoding, UTF_16LE);
+    assert_eq!(detect(&utf16("<?xml", true)).encoding, UTF_16BE);
+}
+
+#[test]
+fn reads_latin_1_documents() {
+    let expected = expected();
+    let FeatureMember::ThoroughfareName { name, .. } = &parse(&document("UTF-8"))[2] else {
+        panic!("expected a thoroughfare");
+    };
+    assert_eq!(name.as_deref(), Some("Âldehôfster Tsjerkhôf"));
+
+    let latin_1 = encode(&document("ISO-8859-1"), WINDOWS_1252);
+    assert!(std::str::from_utf8(&latin_1).is_err());
+    assert_eq!(descriptions(&latin_1, None), expected);
+}
+
+#[test]
+fn reads_utf_16_documents_with_a_bom() {
+    let expected = expected();
+    for big_endian in [false, true] {
+        let utf_16 = utf16(&format!("\u{FEFF}{}", document("UTF-16")), big_endian);
+        assert_eq!(descriptions(&utf_16, None), expected, "{}", big_endian);
+    }
+}
+
+#[test]
+fn overrides_the_declared_encoding() {
+    // A Latin-1 document that claims to be UTF-8
+    let latin_1 = encode(&document("UTF-8"), WINDOWS_1252);
+    assert_eq!(descriptions(&latin_1, Some(WINDOWS_1252)), expected());
+}
+
+#[test]
+fn fails_on_unknown_encodings() {
+    let document = document("X-KLINGON");
+    assert!(matches!(
+        decode(document.as_bytes(), None),
+        Err(Error::UnknownEncoding(label)) if label == "X-KLINGON"
+    ));
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use std::num::NonZeroUsize;
+
+use common::{address, document, thoroughfare};
+use gmlparser::{Error, ParallelParser, Parser};
+
+/// A document with an invalid date, whose text starts on line 7.
+fn invalid_document() -> String {
+    let invalid = address("0003", &[]).replace(
+        "</ad:Address>",
+        "<ad:beginLifespanVersion>\n  yesterday\n</ad:beginLifespanVersion></ad:Address>",
+    );
+    document(&[
+        thoroughfare("t1", "0003300000116985", "Dijkstraat"),
+        // Line breaks in markup count too
+        address("0001", &["#t1"]).replace(" gml:id", "\n  gml:id"),
+        address("0002", &["#t1"]),
+        invalid,
+    ])
+}
+
+fn line(error: Error) -> (u64, u64) {
+    match error {
+        Error::Parse { line, position, .. } | Error::Xml { line, position, .. } => (line, position),
+        error => panic!("expected a parse error, got {}", error),
+    }
+}
+
+#[test]
+fn reports_the_line_of_an_error() {
+    let document = invalid_document();
+    // The text of the date starts on the line of its start tag
+    let position = document.find("<ad:beginLifespanVersion>").unwrap() as u64 + 25;
+    assert_eq!(document[..position as usize].lines().count(), 7);
+
+    let error = Parser::new(document.as_bytes())
+        .into_iter()
+        .find_map(Result::err)
+        .unwrap();
+    assert!(
+        error
+            .to_string()
+            .starts_with("parse error at line 7 (byte "),
+        "{}",
+        error
+    );
+    assert_eq!(line(error), (7, position));
+
+    let error = Parser::new(document.as_bytes())
+        .into_borrowed_iter()
+        .find_map(Result::err)
+        .unwrap();
+    assert_eq!(line(error), (7, position));
+
+    // Every feature member in a chunk of its own
+    let error = ParallelParser::new(document.as_bytes())
+        .with_threads(NonZeroUsize::new(2).unwrap())
+        .with_chunk_size(1)
+        .for_each(|_| Ok(()))
+        .unwrap_err();
+    assert_eq!(line(error), (7, position));
+}
+
+#[test]
+fn reports_the_line_of_malformed_xml() {
+    let document = document(&[address("0001", &[]), address("0002", &[])])
+        .replacen("</ad:Address>", "</ad:Adress>", 2)
+        .replacen("</ad:Adress>", "</ad:Address>", 1);
+    let position = document.find("</ad:Adress>").unwrap() as u64;
+
+    let error = Parser::new(document.as_bytes())
+        .into_iter()
+        .find_map(Result::err)
+        .unwrap();
+    assert!(
+        error.to_string().starts_with("XML error at line 4 (byte "),
+        "{}",
+        error
+    );
+    assert_eq!(line(error), (4, position));
+
+    for chunk_size in [1, 1 << 20] {
+        let error = ParallelParser::new(document.as_bytes())
+            .with_threads(NonZeroUsize::new(2).unwrap())
+            .with_chunk_size(chunk_size)
+            .for_each(|_| Ok(()))
+            .unwrap_err();
+        assert!(matches!(error, Error::Xml { .. }), "{}", error);
+        assert_eq!(line(error), (4, position), "chunks of {} bytes", chunk_size);
+    }
+}
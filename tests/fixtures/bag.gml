@@ -0,0 +1,238 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<gml:FeatureCollection xmlns:gml="http://www.opengis.net/gml/3.2" xmlns:ad="http://inspire.ec.europa.eu/schemas/ad/4.0" xmlns:base="http://inspire.ec.europa.eu/schemas/base/3.3" xmlns:gn="http://inspire.ec.europa.eu/schemas/gn/4.0" xmlns:xlink="http://www.w3.org/1999/xlink" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" gml:id="bag">
+  <gml:featureMember>
+    <ad:AdminUnitName gml:id="nl-imbag-ad-adminunitname.0080">
+      <ad:inspireId>
+        <base:Identifier>
+          <base:localId>0080</base:localId>
+          <base:namespace>NL.IMBAG.Gemeente</base:namespace>
+        </base:Identifier>
+      </ad:inspireId>
+      <ad:beginLifespanVersion>2018-01-01T00:00:00</ad:beginLifespanVersion>
+      <ad:status xlink:href="http://inspire.ec.europa.eu/codelist/StatusValue/current"/>
+      <ad:validFrom>2018-01-01T00:00:00</ad:validFrom>
+      <ad:name>
+        <gn:GeographicalName>
+          <gn:language>dut</gn:language>
+          <gn:nativeness xlink:href="http://inspire.ec.europa.eu/codelist/NativenessValue/endonym"/>
+          <gn:nameStatus xlink:href="http://inspire.ec.europa.eu/codelist/NameStatusValue/official"/>
+          <gn:sourceOfName>BAG</gn:sourceOfName>
+          <gn:pronunciation xsi:nil="true"/>
+          <gn:spelling>
+            <gn:SpellingOfName>
+              <gn:text>Leeuwarden</gn:text>
+              <gn:script>Latn</gn:script>
+            </gn:SpellingOfName>
+          </gn:spelling>
+        </gn:GeographicalName>
+      </ad:name>
+      <ad:name>
+        <gn:GeographicalName>
+          <gn:language>fry</gn:language>
+          <gn:nameStatus xlink:href="http://inspire.ec.europa.eu/codelist/NameStatusValue/standardised"/>
+          <gn:spelling>
+            <gn:SpellingOfName>
+              <gn:text>Ljouwert</gn:text>
+              <gn:script>Latn</gn:script>
+            </gn:SpellingOfName>
+          </gn:spelling>
+        </gn:GeographicalName>
+      </ad:name>
+      <ad:level xlink:href="http://inspire.ec.europa.eu/codelist/AdministrativeHierarchyLevel/4thOrder"/>
+    </ad:AdminUnitName>
+  </gml:featureMember>
+  <gml:featureMember>
+    <ad:AddressAreaName gml:id="nl-imbag-ad-addressareaname.3381">
+      <ad:inspireId>
+        <base:Identifier>
+          <base:localId>3381</base:localId>
+          <base:namespace>NL.IMBAG.Woonplaats</base:namespace>
+          <base:versionId>2</base:versionId>
+        </base:Identifier>
+      </ad:inspireId>
+      <ad:beginLifespanVersion>2010-06-14T11:30:00.123</ad:beginLifespanVersion>
+      <ad:status xlink:href="http://inspire.ec.europa.eu/codelist/StatusValue/current"/>
+      <ad:validFrom>2010-06-14</ad:validFrom>
+      <ad:name>
+        <gn:GeographicalName>
+          <gn:language>dut</gn:language>
+          <gn:spelling>
+            <gn:SpellingOfName>
+              <gn:text>Leeuwarden</gn:text>
+            </gn:SpellingOfName>
+          </gn:spelling>
+        </gn:GeographicalName>
+      </ad:name>
+      <ad:situatedWithin xlink:href="#nl-imbag-ad-adminunitname.0080"/>
+    </ad:AddressAreaName>
+  </gml:featureMember>
+  <gml:featureMember>
+    <ad:ThoroughfareName gml:id="nl-imbag-ad-thoroughfarename.0080300000000112">
+      <ad:inspireId>
+        <base:Identifier>
+          <base:localId>0080300000000112</base:localId>
+          <base:namespace>NL.IMBAG.OpenbareRuimte</base:namespace>
+        </base:Identifier>
+      </ad:inspireId>
+      <ad:beginLifespanVersion>2009-12-01T00:00:00</ad:beginLifespanVersion>
+      <ad:status xlink:href="http://inspire.ec.europa.eu/codelist/StatusValue/current"/>
+      <ad:validFrom>2009-12-01T00:00:00</ad:validFrom>
+      <ad:name>
+        <ad:ThoroughfareNameValue>
+          <ad:name>
+            <gn:GeographicalName>
+              <gn:language>dut</gn:language>
+              <gn:spelling>
+                <gn:SpellingOfName>
+                  <gn:text>Oldehoofsterkerkhof</gn:text>
+                </gn:SpellingOfName>
+              </gn:spelling>
+            </gn:GeographicalName>
+          </ad:name>
+        </ad:ThoroughfareNameValue>
+      </ad:name>
+      <ad:situatedWithin xlink:href="#nl-imbag-ad-addressareaname.3381"/>
+    </ad:ThoroughfareName>
+  </gml:featureMember>
+  <gml:featureMember>
+    <ad:PostalDescriptor gml:id="nl-imbag-ad-postaldescriptor.8911DJ">
+      <ad:inspireId>
+        <base:Identifier>
+          <base:localId>8911DJ</base:localId>
+          <base:namespace>NL.IMBAG.Postcode</base:namespace>
+        </base:Identifier>
+      </ad:inspireId>
+      <ad:beginLifespanVersion>2009-12-01T00:00:00</ad:beginLifespanVersion>
+      <ad:postName>
+        <gn:GeographicalName>
+          <gn:spelling>
+            <gn:SpellingOfName>
+              <gn:text>Leeuwarden</gn:text>
+            </gn:SpellingOfName>
+          </gn:spelling>
+        </gn:GeographicalName>
+      </ad:postName>
+      <ad:postCode>8911DJ</ad:postCode>
+      <ad:situatedWithin xlink:href="#nl-imbag-ad-adminunitname.0080"/>
+    </ad:PostalDescriptor>
+  </gml:featureMember>
+  <gml:featureMember>
+    <ad:Address gml:id="nl-imbag-ad-address.0080200000021745">
+      <ad:inspireId>
+        <base:Identifier>
+          <base:localId>0080200000021745</base:localId>
+          <base:namespace>NL.IMBAG.Nummeraanduiding</base:namespace>
+          <base:versionId>3</base:versionId>
+        </base:Identifier>
+      </ad:inspireId>
+      <ad:alternativeIdentifier xsi:nil="true"/>
+      <ad:position>
+        <ad:GeographicPosition>
+          <ad:geometry>
+            <gml:Point gml:id="nl-imbag-ad-address.0080200000021745.position" srsName="urn:ogc:def:crs:EPSG::28992" srsDimension="2">
+              <gml:pos>182446.0 579344.0</gml:pos>
+            </gml:Point>
+          </ad:geometry>
+          <ad:specification xlink:href="http://inspire.ec.europa.eu/codelist/GeometrySpecificationValue/entrance"/>
+          <ad:method xlink:href="http://inspire.ec.europa.eu/codelist/GeometryMethodValue/byOtherParty"/>
+          <ad:default>true</ad:default>
+        </ad:GeographicPosition>
+      </ad:position>
+      <ad:status xlink:href="http://inspire.ec.europa.eu/codelist/StatusValue/current"/>
+      <ad:locator>
+        <ad:AddressLocator>
+          <ad:designator>
+            <ad:LocatorDesignator>
+              <ad:designator>2</ad:designator>
+              <ad:type xlink:href="http://inspire.ec.europa.eu/codelist/LocatorDesignatorTypeValue/addressNumber"/>
+            </ad:LocatorDesignator>
+          </ad:designator>
+          <ad:designator>
+            <ad:LocatorDesignator>
+              <ad:designator>A</ad:designator>
+              <ad:type xlink:href="http://inspire.ec.europa.eu/codelist/LocatorDesignatorTypeValue/addressNumberExtension"/>
+            </ad:LocatorDesignator>
+          </ad:designator>
+          <ad:designator>
+            <ad:LocatorDesignator>
+              <ad:designator>8911DJ</ad:designator>
+              <ad:type xlink:href="http://inspire.ec.europa.eu/codelist/LocatorDesignatorTypeValue/postalDeliveryIdentifier"/>
+            </ad:LocatorDesignator>
+          </ad:designator>
+          <ad:level xlink:href="http://inspire.ec.europa.eu/codelist/LocatorLevelValue/unitLevel"/>
+        </ad:AddressLocator>
+      </ad:locator>
+      <ad:locator>
+        <ad:AddressLocator>
+          <ad:name>
+            <ad:LocatorName>
+              <ad:name>
+                <gn:GeographicalName>
+                  <gn:language>dut</gn:language>
+                  <gn:spelling>
+                    <gn:SpellingOfName>
+                      <gn:text>Oldehove</gn:text>
+                    </gn:SpellingOfName>
+                  </gn:spelling>
+                </gn:GeographicalName>
+              </ad:name>
+              <ad:type xlink:href="http://inspire.ec.europa.eu/codelist/LocatorNameTypeValue/buildingName"/>
+            </ad:LocatorName>
+          </ad:name>
+          <ad:level xlink:href="http://inspire.ec.europa.eu/codelist/LocatorLevelValue/siteLevel"/>
+        </ad:AddressLocator>
+      </ad:locator>
+      <ad:validFrom>2010-03-29T00:00:00</ad:validFrom>
+      <ad:validTo xsi:nil="true"/>
+      <ad:beginLifespanVersion>2018-06-21T09:15:42</ad:beginLifespanVersion>
+      <ad:parentAddress xlink:href="#nl-imbag-ad-address.0080200000021746"/>
+      <ad:building xlink:href="http://bag.basisregistraties.overheid.nl/bag/id/verblijfsobject/0080010000021746"/>
+      <ad:component xlink:href="#nl-imbag-ad-thoroughfarename.0080300000000112"/>
+      <ad:component xlink:href="#nl-imbag-ad-addressareaname.3381"/>
+      <ad:component xlink:href="#nl-imbag-ad-adminunitname.0080"/>
+      <ad:component xlink:href="#nl-imbag-ad-postaldescriptor.8911DJ"/>
+    </ad:Address>
+  </gml:featureMember>
+  <gml:featureMember>
+    <ad:Address gml:id="nl-imbag-ad-address.0080200000021746">
+      <ad:inspireId>
+        <base:Identifier>
+          <base:localId>0080200000021746</base:localId>
+          <base:namespace>NL.IMBAG.Nummeraanduiding</base:namespace>
+        </base:Identifier>
+      </ad:inspireId>
+      <ad:position>
+        <ad:GeographicPosition>
+          <ad:geometry>
+            <gml:Point srsName="urn:ogc:def:crs:EPSG::28992">
+              <gml:pos>182450.5 579340.25</gml:pos>
+            </gml:Point>
+          </ad:geometry>
+          <ad:default>false</ad:default>
+        </ad:GeographicPosition>
+      </ad:position>
+      <ad:status xlink:href="http://inspire.ec.europa.eu/codelist/StatusValue/retired"/>
+      <ad:locator>
+        <ad:AddressLocator>
+          <ad:designator>
+            <ad:LocatorDesignator>
+              <ad:designator>4</ad:designator>
+              <ad:type xlink:href="http://inspire.ec.europa.eu/codelist/LocatorDesignatorTypeValue/addressNumber"/>
+            </ad:LocatorDesignator>
+          </ad:designator>
+          <ad:level xlink:href="http://inspire.ec.europa.eu/codelist/LocatorLevelValue/unitLevel"/>
+        </ad:AddressLocator>
+      </ad:locator>
+      <ad:validFrom>2010-03-29T00:00:00</ad:validFrom>
+      <ad:validTo>2021-12-31T23:59:59</ad:validTo>
+      <ad:beginLifespanVersion>2018-06-21T09:15:42</ad:beginLifespanVersion>
+      <ad:endLifespanVersion>2022-01-01T00:00:00</ad:endLifespanVersion>
+      <ad:building xlink:href="http://bag.basisregistraties.overheid.nl/bag/id/verblijfsobject/0080010000021747"/>
+      <ad:building xlink:href="http://bag.basisregistraties.overheid.nl/bag/id/pand/0080100000300394"/>
+      <ad:component xlink:href="#nl-imbag-ad-thoroughfarename.0080300000000112"/>
+      <ad:component xlink:href="#nl-imbag-ad-addressareaname.3381"/>
+      <ad:component xlink:href="#nl-imbag-ad-adminunitname.0080"/>
+      <ad:component xlink:href="#nl-imbag-ad-postaldescriptor.8911DJ"/>
+    </ad:Address>
+  </gml:featureMember>
+</gml:FeatureCollection>
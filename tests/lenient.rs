@@ -0,0 +1,73 @@
+mod common;
+
+use common::{address, document, local_id, temp_dir, thoroughfare};
+use gmlparser::{Error, Mode, Parser, error::ParseErrorKind};
+
+fn unknown_members_document() -> String {
+    document(&[
+        thoroughfare("t1", "0003300000116985", "Dijkstraat"),
+        "<ad:Foo><ad:bar>1</ad:bar></ad:Foo>".to_string(),
+        "<ad:Foo/>".to_string(),
+        address("0001", &["#t1"]),
+    ])
+}
+
+fn kind(error: Error) -> ParseErrorKind {
+    match error {
+        Error::Parse { kind, .. } => kind,
+        error => panic!("expected a parse error, got {}", error),
+    }
+}
+
+#[test]
+fn skips_unknown_members_in_lenient_mode() {
+    let dir = temp_dir("lenient");
+    let rejects = dir.join("rejects.xml");
+    let document = unknown_members_document();
+    let mut feature_members = Parser::new(document.as_bytes())
+        .with_mode(Mode::Lenient)
+        .with_rejects(std::fs::File::create(&rejects).unwrap())
+        .into_iter();
+    let local_ids: Vec<_> = (&mut feature_members)
+        .map(|feature_member| local_id(&feature_member.unwrap()))
+        .collect();
+
+    assert_eq!(local_ids, ["0003300000116985", "0001"]);
+    let skipped: Vec<_> = feature_members.skipped().iter().collect();
+    assert_eq!(
+        skipped,
+        [(
+            &"{http://inspire.ec.europa.eu/schemas/ad/4.0}Foo".to_string(),
+            &2
+        )]
+    );
+    drop(feature_members);
+    assert_eq!(
+        std::fs::read_to_string(&rejects).unwrap(),
+        "<ad:Foo><ad:bar>1</ad:bar></ad:Foo>\n<ad:Foo/>\n"
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fails_on_unknown_members_in_strict_mode() {
+    let document = document(&["<ad:Foo/>".to_string()]);
+    let error = Parser::new(document.as_bytes())
+        .into_iter()
+        .find_map(Result::err)
+        .unwrap();
+    assert!(
+        matches!(kind(error), ParseErrorKind::UnknownFeatureMember(tag) if tag.ends_with("}Foo"))
+    );
+}
+
+#[test]
+fn fails_on_an_empty_address() {
+    let document = document(&["<ad:Address/>".to_string()]);
+    let error = Parser::new(document.as_bytes())
+        .with_mode(Mode::Lenient)
+        .into_iter()
+        .find_map(Result::err)
+        .unwrap();
+    assert!(matches!(kind(error), ParseErrorKind::MissingLocalId));
+}
@@ -0,0 +1,206 @@
+mod common;
+
+use chrono::NaiveDateTime;
+use common::{address, document, fixture, local_id, parse};
+use gmlparser::{FeatureMember, Identifier, Lifecycle};
+
+fn lifecycle<'f>(feature_member: &'f FeatureMember<'_>) -> &'f Lifecycle {
+    match feature_member {
+        FeatureMember::Address { lifecycle, .. }
+        | FeatureMember::AdminUnitName { lifecycle, .. }
+        | FeatureMember::AddressAreaName { lifecycle, .. }
+        | FeatureMember::ThoroughfareName { lifecycle, .. }
+        | FeatureMember::PostalDescriptor { lifecycle, .. } => lifecycle,
+    }
+}
+
+fn identifier<'f>(feature_member: &'f FeatureMember<'_>) -> &'f Identifier<'f> {
+    match feature_member {
+        FeatureMember::Address { identifier, .. }
+        | FeatureMember::AdminUnitName { identifier, .. }
+        | FeatureMember::AddressAreaName { identifier, .. }
+        | FeatureMember::ThoroughfareName { identifier, .. }
+        | FeatureMember::PostalDescriptor { identifier, .. } => identifier,
+    }
+}
+
+fn date_time(date_time: Option<NaiveDateTime>) -> Option<String> {
+    date_time.map(|date_time| date_time.to_string())
+}
+
+#[test]
+fn reads_the_lifecycle_of_every_feature_type() {
+    let feature_members = parse(&fixture("bag.gml"));
+    let lifecycles: Vec<_> = feature_members
+        .iter()
+        .map(|feature_member| {
+            let lifecycle = lifecycle(feature_member);
+            (
+                lifecycle.status.as_deref(),
+                date_time(lifecycle.valid_from),
+                date_time(lifecycle.valid_to),
+                date_time(lifecycle.begin_lifespan_version),
+                date_time(lifecycle.end_lifespan_version),
+            )
+        })
+        .collect();
+    let some = |text: &str| Some(text.to_string());
+    assert_eq!(
+        lifecycles,
+        [
+            (
+                Some("current"),
+                some("2018-01-01 00:00:00"),
+                None,
+                some("2018-01-01 00:00:00"),
+                None
+            ),
+            // A date without a time, and fractional seconds
+            (
+                Some("current"),
+                some("2010-06-14 00:00:00"),
+                None,
+                some("2010-06-14 11:30:00.123"),
+                None
+            ),
+            (
+                Some("current"),
+                some("2009-12-01 00:00:00"),
+                None,
+                some("2009-12-01 00:00:00"),
+                None
+            ),
+            (None, None, None, some("2009-12-01 00:00:00"), None),
+            // A nil validTo
+            (
+                Some("current"),
+                some("2010-03-29 00:00:00"),
+                None,
+                some("2018-06-21 09:15:42"),
+                None
+            ),
+            (
+                Some("retired"),
+                some("2010-03-29 00:00:00"),
+                some("2021-12-31 23:59:59"),
+                some("2018-06-21 09:15:42"),
+                some("2022-01-01 00:00:00")
+            ),
+        ]
+    );
+}
+
+#[test]
+fn converts_time_zones_to_utc() {
+    let feature = address("0001", &[]).replace(
+        "</ad:Address>",
+        "<ad:validFrom>2024-05-01T12:00:00+02:00</ad:validFrom><ad:validTo>2024-05-01T12:00:00Z</ad:validTo></ad:Address>",
+    );
+    let feature_members = parse(&document(&[feature]));
+    let lifecycle = lifecycle(&feature_members[0]);
+    assert_eq!(
+        date_time(lifecycle.valid_from).as_deref(),
+        Some("2024-05-01 10:00:00")
+    );
+    assert_eq!(
+        date_time(lifecycle.valid_to).as_deref(),
+        Some("2024-05-01 12:00:00")
+    );
+}
+
+#[test]
+fn reads_nil_dates_written_across_lines() {
+    let document = fixture("bag.gml").replace(
+        r#"<ad:validTo xsi:nil="true"/>"#,
+        "<ad:validTo xsi:nil=\"true\">\n      </ad:validTo>",
+    );
+    let feature_members = parse(&document);
+    let lifecycle = lifecycle(&feature_members[4]);
+    assert_eq!(
+        date_time(lifecycle.valid_from).as_deref(),
+        Some("2010-03-29 00:00:00")
+    );
+    assert_eq!(lifecycle.valid_to, None);
+}
+
+#[test]
+fn ignores_dates_of_nested_features() {
+    // Only direct children of the feature are its life-cycle properties
+    let feature = address("0001", &[]).replace(
+        "</ad:Address>",
+        "<ad:locator><ad:AddressLocator><ad:validFrom>2024-05-01</ad:validFrom></ad:AddressLocator></ad:locator></ad:Address>",
+    );
+    let feature_members = parse(&document(&[feature]));
+    assert_eq!(lifecycle(&feature_members[0]).valid_from, None);
+}
+
+#[test]
+fn reads_identifiers() {
+    let feature_members = parse(&fixture("bag.gml"));
+    let identifiers: Vec<_> = feature_members
+        .iter()
+        .map(|feature_member| {
+            let identifier = identifier(feature_member);
+            (
+                local_id(feature_member),
+                identifier.version_id.as_deref(),
+                identifier.namespace.as_deref(),
+                identifier.gml_id.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        identifiers,
+        [
+            (
+                "0080".to_string(),
+                None,
+                Some("NL.IMBAG.Gemeente"),
+                Some("nl-imbag-ad-adminunitname.0080")
+            ),
+            (
+                "3381".to_string(),
+                Some("2"),
+                Some("NL.IMBAG.Woonplaats"),
+                Some("nl-imbag-ad-addressareaname.3381")
+            ),
+            (
+                "0080300000000112".to_string(),
+                None,
+                Some("NL.IMBAG.OpenbareRuimte"),
+                Some("nl-imbag-ad-thoroughfarename.0080300000000112")
+            ),
+            (
+                "8911DJ".to_string(),
+                None,
+                Some("NL.IMBAG.Postcode"),
+                Some("nl-imbag-ad-postaldescriptor.8911DJ")
+            ),
+            (
+                "0080200000021745".to_string(),
+                Some("3"),
+                Some("NL.IMBAG.Nummeraanduiding"),
+                Some("nl-imbag-ad-address.0080200000021745")
+            ),
+            (
+                "0080200000021746".to_string(),
+                None,
+                Some("NL.IMBAG.Nummeraanduiding"),
+                Some("nl-imbag-ad-address.0080200000021746")
+            ),
+        ]
+    );
+}
+
+#[test]
+fn ignores_identifiers_of_nested_elements() {
+    // The gml:id of the point is not the address's
+    let feature = address("0001", &[]).replace(
+        "</ad:Address>",
+        r#"<ad:position><ad:GeographicPosition><ad:geometry><gml:Point gml:id="p1"><gml:pos>1 2</gml:pos></gml:Point></ad:geometry></ad:GeographicPosition></ad:position></ad:Address>"#,
+    );
+    let feature_members = parse(&document(&[feature]));
+    let identifier = identifier(&feature_members[0]);
+    assert_eq!(identifier.gml_id.as_deref(), Some("a0001"));
+    assert_eq!(local_id(&feature_members[0]), "0001");
+}
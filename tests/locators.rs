@@ -0,0 +1,183 @@
+mod common;
+
+use common::{document, fixture, inspire_id, parse, temp_dir};
+use gmlparser::emitter::sqlite::SqliteEmitter;
+use gmlparser::{FeatureMember, Locator, Parser};
+
+const CODELIST: &str = "http://inspire.ec.europa.eu/codelist";
+
+/// Writes out the level, designators and names of each locator.
+fn locators(locators: &[Locator<'_>]) -> Vec<String> {
+    locators
+        .iter()
+        .map(|locator| {
+            let designators = locator.designators.iter().map(|designator| {
+                format!(
+                    "{} {}",
+                    designator.designator_type.as_deref().unwrap_or("-"),
+                    designator.designator.as_deref().unwrap_or("-")
+                )
+            });
+            let names = locator.names.iter().map(|name| {
+                let texts: Vec<_> = name
+                    .names
+                    .iter()
+                    .flat_map(|name| &name.spellings)
+                    .map(|spelling| spelling.text.as_ref())
+                    .collect();
+                format!(
+                    "{} {}",
+                    name.name_type.as_deref().unwrap_or("-"),
+                    texts.join("/")
+                )
+            });
+            format!(
+                "{}: {}",
+                locator.level.as_deref().unwrap_or("-"),
+                designators.chain(names).collect::<Vec<_>>().join(", ")
+            )
+        })
+        .collect()
+}
+
+fn designator(designator_type: &str, designator: &str) -> String {
+    format!(
+        r#"<ad:designator><ad:LocatorDesignator><ad:designator>{}</ad:designator><ad:type xlink:href="{}/LocatorDesignatorTypeValue/{}"/></ad:LocatorDesignator></ad:designator>"#,
+        designator, CODELIST, designator_type
+    )
+}
+
+fn locator(level: &str, designators: &[String]) -> String {
+    format!(
+        r#"<ad:locator><ad:AddressLocator>{}<ad:level xlink:href="{}/LocatorLevelValue/{}"/></ad:AddressLocator></ad:locator>"#,
+        designators.concat(),
+        CODELIST,
+        level
+    )
+}
+
+#[test]
+fn reads_every_locator_of_an_address() {
+    let feature_members = parse(&fixture("bag.gml"));
+    let FeatureMember::Address {
+        number,
+        number_extension,
+        number_2nd_extension,
+        postal_delivery_identifier,
+        locators: address_locators,
+        ..
+    } = &feature_members[4]
+    else {
+        panic!("expected an address");
+    };
+    assert_eq!(
+        locators(address_locators),
+        [
+            "unitLevel: addressNumber 2, addressNumberExtension A, postalDeliveryIdentifier 8911DJ",
+            "siteLevel: buildingName Oldehove",
+        ]
+    );
+    assert_eq!(number.as_deref(), Some("2"));
+    assert_eq!(number_extension.as_deref(), Some("A"));
+    assert_eq!(number_2nd_extension.as_deref(), None);
+    assert_eq!(postal_delivery_identifier.as_deref(), Some("8911DJ"));
+}
+
+#[test]
+fn takes_the_first_designator_of_each_type() {
+    let feature = format!(
+        r#"<ad:Address gml:id="a1">{}{}{}</ad:Address>"#,
+        inspire_id("0001", "NL.IMBAG.Nummeraanduiding"),
+        locator(
+            "siteLevel",
+            &[
+                designator("addressNumber", "12"),
+                designator("addressNumber2ndExtension", "bis"),
+            ]
+        ),
+        locator(
+            "unitLevel",
+            &[
+                designator("addressNumber", "14"),
+                designator("addressNumberExtension", "B"),
+            ]
+        ),
+    );
+    let feature_members = parse(&document(&[feature]));
+    let FeatureMember::Address {
+        number,
+        number_extension,
+        number_2nd_extension,
+        postal_delivery_identifier,
+        locators: address_locators,
+        ..
+    } = &feature_members[0]
+    else {
+        panic!("expected an address");
+    };
+    assert_eq!(
+        locators(address_locators),
+        [
+            "siteLevel: addressNumber 12, addressNumber2ndExtension bis",
+            "unitLevel: addressNumber 14, addressNumberExtension B",
+        ]
+    );
+    assert_eq!(number.as_deref(), Some("12"));
+    assert_eq!(number_extension.as_deref(), Some("B"));
+    assert_eq!(number_2nd_extension.as_deref(), Some("bis"));
+    assert_eq!(postal_delivery_identifier.as_deref(), None);
+}
+
+#[test]
+fn writes_every_designator_and_name_to_sqlite() {
+    let dir = temp_dir("locators");
+    let db_path = dir.join("nl-addresses.sqlite");
+    let emitter = SqliteEmitter::new(&db_path).unwrap();
+    emitter.create_tables().unwrap();
+    Parser::new(fixture("bag.gml").as_bytes())
+        .parse(emitter)
+        .unwrap();
+
+    let db = rusqlite::Connection::open(&db_path).unwrap();
+    let mut statement = db
+        .prepare(
+            "SELECT locator, level, kind, type, value FROM address_locators
+            WHERE address_local_id = '0080200000021745' ORDER BY rowid",
+        )
+        .unwrap();
+    let rows: Vec<String> = statement
+        .query_map([], |row| {
+            Ok(format!(
+                "{} {} {} {} {}",
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?
+            ))
+        })
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        [
+            "0 unitLevel designator addressNumber 2",
+            "0 unitLevel designator addressNumberExtension A",
+            "0 unitLevel designator postalDeliveryIdentifier 8911DJ",
+            "1 siteLevel name buildingName Oldehove",
+        ]
+    );
+
+    let number: String = db
+        .query_row(
+            "SELECT number FROM addresses WHERE local_id = '0080200000021745'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(number, "2");
+    drop(statement);
+    drop(db);
+    std::fs::remove_dir_all(dir).unwrap();
+}
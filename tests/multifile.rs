@@ -0,0 +1,55 @@
+mod common;
+
+use common::{address, document, fixture, temp_dir, thoroughfare};
+use gmlparser::Parser;
+use gmlparser::emitter::multifile::MultiFileEmitter;
+
+const FILES: [&str; 7] = [
+    "addresses.tsv",
+    "admin_units.tsv",
+    "address_areas.tsv",
+    "thoroughfares.tsv",
+    "postal_descriptors.tsv",
+    "address_relations.tsv",
+    "address_locators.tsv",
+];
+
+#[test]
+fn writes_as_many_fields_as_there_are_columns() {
+    let output_dir = temp_dir("multifile-columns");
+    Parser::new(fixture("bag.gml").as_bytes())
+        .parse(MultiFileEmitter::new(&output_dir).unwrap())
+        .unwrap();
+
+    for file in FILES {
+        let tsv = std::fs::read_to_string(output_dir.join(file)).unwrap();
+        let mut lines = tsv.lines();
+        let columns = lines.next().unwrap().split('\t').count();
+        for line in lines {
+            assert_eq!(line.split('\t').count(), columns, "{}: {:?}", file, line);
+        }
+    }
+    std::fs::remove_dir_all(output_dir).unwrap();
+}
+
+#[test]
+fn escapes_tabs_and_line_breaks() {
+    let output_dir = temp_dir("multifile-escape");
+    let document = document(&[
+        thoroughfare("t1", "0003300000116985", "Foo&#9;Bar&#10;Baz\\Qux"),
+        address("0001", &["#t1"]),
+    ]);
+    Parser::new(document.as_bytes())
+        .parse(MultiFileEmitter::new(&output_dir).unwrap())
+        .unwrap();
+
+    let tsv = std::fs::read_to_string(output_dir.join("thoroughfares.tsv")).unwrap();
+    let rows: Vec<_> = tsv.lines().skip(1).collect();
+    assert_eq!(rows.len(), 1);
+    assert!(
+        rows[0].starts_with("0003300000116985\tFoo\\tBar\\nBaz\\\\Qux\t\t"),
+        "{:?}",
+        rows[0]
+    );
+    std::fs::remove_dir_all(output_dir).unwrap();
+}
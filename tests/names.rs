@@ -0,0 +1,111 @@
+mod common;
+
+use common::{document, fixture, inspire_id, parse, temp_dir};
+use gmlparser::emitter::sqlite::SqliteEmitter;
+use gmlparser::{FeatureMember, GeographicalName, Parser};
+
+/// Writes out the language, status and spellings of each name.
+fn names(names: &[GeographicalName<'_>]) -> Vec<String> {
+    names
+        .iter()
+        .map(|name| {
+            let spellings: Vec<_> = name
+                .spellings
+                .iter()
+                .map(|spelling| {
+                    format!(
+                        "{} ({})",
+                        spelling.text,
+                        spelling.script.as_deref().unwrap_or("-")
+                    )
+                })
+                .collect();
+            format!(
+                "{} {}: {}",
+                name.language.as_deref().unwrap_or("-"),
+                name.name_status.as_deref().unwrap_or("-"),
+                spellings.join(", ")
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn reads_every_name_of_a_feature() {
+    let feature_members = parse(&fixture("bag.gml"));
+    let FeatureMember::AdminUnitName {
+        name,
+        names: admin_unit_names,
+        ..
+    } = &feature_members[0]
+    else {
+        panic!("expected an admin unit");
+    };
+    assert_eq!(name.as_deref(), Some("Leeuwarden"));
+    assert_eq!(
+        names(admin_unit_names),
+        [
+            "dut official: Leeuwarden (Latn)",
+            "fry standardised: Ljouwert (Latn)"
+        ]
+    );
+
+    let FeatureMember::ThoroughfareName {
+        name,
+        names: thoroughfare_names,
+        ..
+    } = &feature_members[2]
+    else {
+        panic!("expected a thoroughfare");
+    };
+    assert_eq!(name.as_deref(), Some("Oldehoofsterkerkhof"));
+    assert_eq!(
+        names(thoroughfare_names),
+        ["dut -: Oldehoofsterkerkhof (-)"]
+    );
+}
+
+#[test]
+fn reads_every_spelling_of_a_name() {
+    let feature = format!(
+        r#"<ad:AddressAreaName gml:id="w1">{}<ad:name><gn:GeographicalName><gn:language>ukr</gn:language><gn:spelling><gn:SpellingOfName><gn:text>Київ</gn:text><gn:script>Cyrl</gn:script></gn:SpellingOfName></gn:spelling><gn:spelling><gn:SpellingOfName><gn:text>Kyiv</gn:text><gn:script>Latn</gn:script></gn:SpellingOfName></gn:spelling></gn:GeographicalName></ad:name></ad:AddressAreaName>"#,
+        inspire_id("1", "UA.Settlement")
+    );
+    let feature_members = parse(&document(&[feature]));
+    let FeatureMember::AddressAreaName {
+        name,
+        names: area_names,
+        ..
+    } = &feature_members[0]
+    else {
+        panic!("expected an address area");
+    };
+    // The first spelling of the first name
+    assert_eq!(name.as_deref(), Some("Київ"));
+    assert_eq!(names(area_names), ["ukr -: Київ (Cyrl), Kyiv (Latn)"]);
+}
+
+#[test]
+fn writes_every_spelling_to_sqlite() {
+    let dir = temp_dir("names");
+    let db_path = dir.join("nl-addresses.sqlite");
+    let emitter = SqliteEmitter::new(&db_path).unwrap();
+    emitter.create_tables().unwrap();
+    Parser::new(fixture("bag.gml").as_bytes())
+        .parse(emitter)
+        .unwrap();
+
+    let db = rusqlite::Connection::open(&db_path).unwrap();
+    let mut statement = db
+        .prepare("SELECT feature_table, local_id FROM names WHERE spelling = 'Ljouwert'")
+        .unwrap();
+    let rows: Vec<(String, String)> = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(rows, [("admin_units".to_string(), "0080".to_string())]);
+    drop(statement);
+    drop(db);
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use common::{describe, fixture, parse};
+use gmlparser::{Error, Parser, error::ParseErrorKind};
+
+/// Binds the namespace of `prefix` to `replacement` instead, or to the default namespace if
+/// `replacement` is empty. Attributes keep their prefix then, as the default namespace doesn't
+/// apply to them.
+fn rebind(document: &str, prefix: &str, replacement: &str) -> String {
+    if replacement.is_empty() {
+        return document
+            .replace(&format!("<{}:", prefix), "<")
+            .replace(&format!("</{}:", prefix), "</")
+            .replace(&format!("xmlns:{}=", prefix), "xmlns=");
+    }
+    document
+        .replace(&format!("<{}:", prefix), &format!("<{}:", replacement))
+        .replace(&format!("</{}:", prefix), &format!("</{}:", replacement))
+        .replace(&format!(" {}:", prefix), &format!(" {}:", replacement))
+        .replace(
+            &format!("xmlns:{}=", prefix),
+            &format!("xmlns:{}=", replacement),
+        )
+}
+
+fn descriptions(document: &str) -> Vec<String> {
+    parse(document).iter().map(describe).collect()
+}
+
+fn parse_error(document: &str) -> ParseErrorKind {
+    match Parser::new(document.as_bytes())
+        .into_iter()
+        .find_map(Result::err)
+    {
+        Some(Error::Parse { kind, .. }) => kind,
+        other => panic!(
+            "expected a parse error, got {:?}",
+            other.map(|e| e.to_string())
+        ),
+    }
+}
+
+#[test]
+fn matches_elements_by_namespace_instead_of_prefix() {
+    let document = fixture("bag.gml");
+    let expected = descriptions(&document);
+    assert_eq!(expected.len(), 6);
+
+    let mut renamed = document.clone();
+    for (prefix, replacement) in [("ad", "a"), ("gn", "n"), ("gml", "g"), ("base", "b")] {
+        renamed = rebind(&renamed, prefix, replacement);
+    }
+    assert!(!renamed.contains("<ad:"));
+    assert_eq!(descriptions(&renamed), expected);
+}
+
+#[test]
+fn reads_elements_in_the_default_namespace() {
+    let document = rebind(&fixture("bag.gml"), "ad", "");
+    assert!(document.contains("<Address "));
+    let expected = descriptions(&fixture("bag.gml"));
+    assert_eq!(descriptions(&document), expected);
+}
+
+#[test]
+fn fails_on_features_of_another_namespace() {
+    // The ad prefix of an older version of the schema
+    let document = fixture("bag.gml").replace(
+        "http://inspire.ec.europa.eu/schemas/ad/4.0",
+        "http://inspire.ec.europa.eu/schemas/ad/3.0",
+    );
+    assert!(matches!(
+        parse_error(&document),
+        ParseErrorKind::UnknownFeatureMember(tag)
+            if tag == "{http://inspire.ec.europa.eu/schemas/ad/3.0}AdminUnitName"
+    ));
+}
+
+#[test]
+fn fails_on_undeclared_prefixes() {
+    let document = fixture("bag.gml").replace(
+        r#" xmlns:gn="http://inspire.ec.europa.eu/schemas/gn/4.0""#,
+        "",
+    );
+    assert!(matches!(
+        parse_error(&document),
+        ParseErrorKind::UnknownPrefix(prefix) if prefix == "gn"
+    ));
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use std::collections::BTreeMap;
+use std::io::{BufReader, Read};
+use std::num::NonZeroUsize;
+
+use common::{
+    NAMESPACES, address, admin_unit, describe, document, fixture, local_id, thoroughfare,
+};
+use gmlparser::{Error, Mode, ParallelParser, Parser};
+
+/// Parses `document` on the main thread, returning the described feature members and the skipped
+/// ones.
+fn sequential(document: &str, mode: Mode) -> (Vec<String>, BTreeMap<String, u64>) {
+    let mut feature_members = Parser::new(document.as_bytes()).with_mode(mode).into_iter();
+    let descriptions = (&mut feature_members)
+        .map(|feature_member| describe(&feature_member.unwrap()))
+        .collect();
+    (descriptions, feature_members.skipped().clone())
+}
+
+fn parallel(
+    document: &str,
+    mode: Mode,
+    threads: usize,
+    chunk_size: usize,
+) -> (Vec<String>, BTreeMap<String, u64>) {
+    let mut parser = ParallelParser::new(document.as_bytes())
+        .with_mode(mode)
+        .with_threads(NonZeroUsize::new(threads).unwrap())
+        .with_chunk_size(chunk_size);
+    let mut descriptions = Vec::new();
+    parser
+        .for_each(|feature_member| {
+            descriptions.push(describe(&feature_member));
+            Ok(())
+        })
+        .unwrap();
+    (descriptions, parser.skipped().clone())
+}
+
+fn assert_same_as_sequential(document: &str, mode: Mode) {
+    let expected = sequential(document, mode);
+    assert!(!expected.0.is_empty());
+    for threads in [1, 2, 4] {
+        for chunk_size in [1, 200, 1000, 1 << 20] {
+            assert_eq!(
+                parallel(document, mode, threads, chunk_size),
+                expected,
+                "{} threads, chunks of {} bytes",
+                threads,
+                chunk_size
+            );
+        }
+    }
+}
+
+#[test]
+fn parses_the_fixture_like_the_sequential_parser() {
+    assert_same_as_sequential(&fixture("bag.gml"), Mode::Strict);
+}
+
+#[test]
+fn resolves_references_across_chunks() {
+    // Addresses refer to names by gml:id, some in earlier chunks and some further on
+    let mut features = vec![admin_unit("g1", "0363", "Amsterdam")];
+    for index in 0..20 {
+        features.push(thoroughfare(
+            &format!("t{}", index),
+            &format!("03633000000{:05}", index),
+            &format!("Straat {}", index),
+        ));
+        features.push(address(
+            &format!("{:04}", index),
+            &["#g1", &format!("#t{}", index), &format!("#t{}", index + 1)],
+        ));
+    }
+    assert_same_as_sequential(&document(&features), Mode::Strict);
+}
+
+#[test]
+fn skips_the_same_feature_members_in_lenient_mode() {
+    let mut features = Vec::new();
+    for index in 0..10 {
+        features.push(address(&format!("{:04}", index), &[]));
+        features.push(format!("<ad:Foo><ad:bar>{}</ad:bar></ad:Foo>", index));
+        features.push("<ad:Bar/>".to_string());
+    }
+    assert_same_as_sequential(&document(&features), Mode::Lenient);
+}
+
+#[test]
+fn splits_wfs_members() {
+    let members: String = (0..10)
+        .map(|index| {
+            format!(
+                "<wfs:member>{}</wfs:member>",
+                address(&index.to_string(), &[])
+            )
+        })
+        .collect();
+    let document = format!(
+        r#"<wfs:FeatureCollection {} numberMatched="10" numberReturned="10">{}</wfs:FeatureCollection>"#,
+        NAMESPACES, members
+    );
+    assert_same_as_sequential(&document, Mode::Strict);
+}
+
+/// Fails every read, standing in for input that should not be read.
+struct Unreadable;
+
+impl Read for Unreadable {
+    fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+        Err(std::io::Error::other("read past the error"))
+    }
+}
+
+#[test]
+fn stops_reading_at_malformed_xml() {
+    let features: Vec<_> = (0..10)
+        .map(|index| address(&format!("{:04}", index), &[]))
+        .collect();
+    let document = document(&features).replacen("</ad:Address>", "</ad:Adress>", 6);
+    let document = document.replacen("</ad:Adress>", "</ad:Address>", 5);
+    // Only the part up to the error fits in the buffer
+    let end = document.find("</ad:Adress>").unwrap() + 200;
+
+    for chunk_size in [1, 1000, 1 << 20] {
+        let reader = BufReader::with_capacity(end, document.as_bytes()[..end].chain(Unreadable));
+        let mut parser = ParallelParser::new(reader)
+            .with_threads(NonZeroUsize::new(2).unwrap())
+            .with_chunk_size(chunk_size);
+        let mut local_ids = Vec::new();
+        let error = parser
+            .for_each(|feature_member| {
+                local_ids.push(local_id(&feature_member));
+                Ok(())
+            })
+            .unwrap_err();
+        assert!(matches!(error, Error::Xml { .. }), "{}", error);
+        // The feature members before the error are still read
+        assert_eq!(local_ids, ["0000", "0001", "0002", "0003", "0004"]);
+    }
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use common::{address, document, fixture, parse};
+use gmlparser::{Error, FeatureMember, Parser, Position, error::ParseErrorKind};
+
+fn positions<'f>(feature_members: &'f [FeatureMember<'_>]) -> Vec<Option<&'f Position>> {
+    feature_members
+        .iter()
+        .filter_map(|feature_member| match feature_member {
+            FeatureMember::Address { position, .. } => Some(position.as_ref()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// An address with a `gml:pos` per position.
+fn address_with_positions(positions: &[(&str, bool)]) -> String {
+    let positions: String = positions
+        .iter()
+        .map(|(pos, default)| {
+            format!(
+                "<ad:position><ad:GeographicPosition><ad:geometry><gml:Point><gml:pos>{}</gml:pos></gml:Point></ad:geometry><ad:default>{}</ad:default></ad:GeographicPosition></ad:position>",
+                pos, default
+            )
+        })
+        .collect();
+    address("0001", &[]).replace("</ad:Address>", &format!("{}</ad:Address>", positions))
+}
+
+#[test]
+fn reads_positions() {
+    let feature_members = parse(&fixture("bag.gml"));
+    let [Some(first), Some(second)] = positions(&feature_members)[..] else {
+        panic!("expected two addresses with a position");
+    };
+    assert_eq!((first.x, first.y), (182446.0, 579344.0));
+    assert_eq!(first.method.as_deref(), Some("byOtherParty"));
+    assert_eq!(first.specification.as_deref(), Some("entrance"));
+    assert!(first.default);
+
+    assert_eq!((second.x, second.y), (182450.5, 579340.25));
+    assert_eq!(second.method, None);
+    assert_eq!(second.specification, None);
+    assert!(!second.default);
+}
+
+#[test]
+fn reads_default_written_as_a_digit() {
+    let document = fixture("bag.gml")
+        .replace(
+            "<ad:default>true</ad:default>",
+            "<ad:default>1</ad:default>",
+        )
+        .replace(
+            "<ad:default>false</ad:default>",
+            "<ad:default> 0 </ad:default>",
+        );
+    let feature_members = parse(&document);
+    let [Some(first), Some(second)] = positions(&feature_members)[..] else {
+        panic!("expected two addresses with a position");
+    };
+    assert!(first.default);
+    assert!(!second.default);
+}
+
+#[test]
+fn prefers_the_default_position() {
+    for positions_in_order in [
+        [("1 2", false), ("3 4", true)],
+        [("3 4", true), ("1 2", false)],
+    ] {
+        let feature_members = parse(&document(&[address_with_positions(&positions_in_order)]));
+        let [Some(position)] = positions(&feature_members)[..] else {
+            panic!("expected an address with a position");
+        };
+        assert_eq!((position.x, position.y), (3.0, 4.0));
+    }
+
+    // Otherwise the last one
+    let feature_members = parse(&document(&[address_with_positions(&[
+        ("1 2", false),
+        ("3 4", false),
+    ])]));
+    let [Some(position)] = positions(&feature_members)[..] else {
+        panic!("expected an address with a position");
+    };
+    assert_eq!((position.x, position.y), (3.0, 4.0));
+}
+
+#[test]
+fn fails_on_an_invalid_position() {
+    let document = document(&[address_with_positions(&[("182446.0", true)])]);
+    let error = Parser::new(document.as_bytes())
+        .into_iter()
+        .find_map(Result::err)
+        .unwrap();
+    assert!(
+        matches!(
+            &error,
+            Error::Parse {
+                kind: ParseErrorKind::InvalidPosition(text),
+                ..
+            } if text == "182446.0"
+        ),
+        "{}",
+        error
+    );
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use common::{address, document, fixture, inspire_id, parse};
+use gmlparser::FeatureMember;
+
+fn postal_descriptor(post_code: &str, post_names: &[&str]) -> String {
+    let post_names: String = post_names
+        .iter()
+        .map(|post_name| {
+            format!(
+                "<ad:postName><gn:GeographicalName><gn:spelling><gn:SpellingOfName><gn:text>{}</gn:text></gn:SpellingOfName></gn:spelling></gn:GeographicalName></ad:postName>",
+                post_name
+            )
+        })
+        .collect();
+    format!(
+        r#"<ad:PostalDescriptor gml:id="nl-imbag-ad-postaldescriptor.{0}">{1}{2}<ad:postCode>{0}</ad:postCode></ad:PostalDescriptor>"#,
+        post_code,
+        inspire_id(post_code, "NL.IMBAG.Postcode"),
+        post_names
+    )
+}
+
+#[test]
+fn reads_postal_descriptors() {
+    let feature_members = parse(&fixture("bag.gml"));
+    let FeatureMember::PostalDescriptor {
+        local_id,
+        post_code,
+        post_name,
+        admin_unit_ref,
+        ..
+    } = &feature_members[3]
+    else {
+        panic!("expected a postal descriptor");
+    };
+    assert_eq!(local_id, "8911DJ");
+    assert_eq!(post_code.as_deref(), Some("8911DJ"));
+    assert_eq!(post_name.as_deref(), Some("Leeuwarden"));
+    let admin_unit_ref = admin_unit_ref.as_ref().unwrap();
+    assert_eq!(admin_unit_ref.href, "#nl-imbag-ad-adminunitname.0080");
+    assert_eq!(admin_unit_ref.local_id, "0080");
+
+    let FeatureMember::Address {
+        postal_descriptor_ref: Some(postal_descriptor_ref),
+        ..
+    } = &feature_members[4]
+    else {
+        panic!("expected an address with a postal descriptor");
+    };
+    assert_eq!(postal_descriptor_ref.local_id, "8911DJ");
+}
+
+#[test]
+fn keeps_the_first_post_name() {
+    let feature_members = parse(&document(&[postal_descriptor(
+        "8911DJ",
+        &["Leeuwarden", "Ljouwert"],
+    )]));
+    let FeatureMember::PostalDescriptor { post_name, .. } = &feature_members[0] else {
+        panic!("expected a postal descriptor");
+    };
+    assert_eq!(post_name.as_deref(), Some("Leeuwarden"));
+}
+
+#[test]
+fn refers_to_postal_descriptors_that_follow() {
+    // Classified by the type in the identifier, as the descriptor is not known yet
+    let feature_members = parse(&document(&[
+        address("0001", &["#nl-imbag-ad-postaldescriptor.1234AB"]),
+        postal_descriptor("1234AB", &["Amsterdam"]),
+    ]));
+    let FeatureMember::Address {
+        postal_descriptor_ref: Some(postal_descriptor_ref),
+        other_component_refs,
+        ..
+    } = &feature_members[0]
+    else {
+        panic!("expected an address with a postal descriptor");
+    };
+    assert_eq!(postal_descriptor_ref.local_id, "1234AB");
+    assert!(other_component_refs.is_empty());
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use common::{address, document, parse, thoroughfare};
+use gmlparser::FeatureMember;
+
+#[test]
+fn keeps_components_of_unknown_type() {
+    let document = document(&[
+        thoroughfare("t1", "0003300000116985", "Dijkstraat"),
+        // The second street only follows the address, and its gml:id doesn't name its type
+        address("0001", &["#t1", "#t2", "urn:x-bag:0003"]),
+        thoroughfare("t2", "0003300000117000", "Kerkstraat"),
+    ]);
+    let feature_members = parse(&document);
+
+    let FeatureMember::Address {
+        thoroughfare_ref,
+        other_component_refs,
+        ..
+    } = &feature_members[1]
+    else {
+        panic!("expected an address");
+    };
+    assert_eq!(
+        thoroughfare_ref
+            .as_ref()
+            .map(|reference| reference.href.as_str()),
+        Some("#t1")
+    );
+    let hrefs: Vec<_> = other_component_refs
+        .iter()
+        .map(|reference| (reference.href.as_str(), reference.local_id.as_str()))
+        .collect();
+    assert_eq!(hrefs, [("#t2", "t2"), ("urn:x-bag:0003", "0003")]);
+}
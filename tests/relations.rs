@@ -0,0 +1,109 @@
+mod common;
+
+use common::{fixture, parse, temp_dir};
+use gmlparser::emitter::sqlite::SqliteEmitter;
+use gmlparser::emitter::validation::{FeatureKind, ValidationEmitter};
+use gmlparser::{FeatureMember, Parser, Reference};
+
+fn local_ids(references: &[Reference]) -> Vec<&str> {
+    references
+        .iter()
+        .map(|reference| reference.local_id.as_str())
+        .collect()
+}
+
+#[test]
+fn reads_the_parent_address_and_buildings() {
+    let feature_members = parse(&fixture("bag.gml"));
+    let FeatureMember::Address {
+        parent_address_ref,
+        building_refs,
+        other_component_refs,
+        ..
+    } = &feature_members[4]
+    else {
+        panic!("expected an address");
+    };
+    let parent_address_ref = parent_address_ref.as_ref().unwrap();
+    assert_eq!(
+        parent_address_ref.href,
+        "#nl-imbag-ad-address.0080200000021746"
+    );
+    assert_eq!(parent_address_ref.local_id, "0080200000021746");
+    assert_eq!(local_ids(building_refs), ["0080010000021746"]);
+    assert!(other_component_refs.is_empty());
+
+    let FeatureMember::Address {
+        parent_address_ref,
+        building_refs,
+        ..
+    } = &feature_members[5]
+    else {
+        panic!("expected an address");
+    };
+    assert!(parent_address_ref.is_none());
+    assert_eq!(
+        local_ids(building_refs),
+        ["0080010000021747", "0080100000300394"]
+    );
+}
+
+#[test]
+fn writes_a_row_per_relation_to_sqlite() {
+    let dir = temp_dir("relations");
+    let db_path = dir.join("nl-addresses.sqlite");
+    let emitter = SqliteEmitter::new(&db_path).unwrap();
+    emitter.create_tables().unwrap();
+    Parser::new(fixture("bag.gml").as_bytes())
+        .parse(emitter)
+        .unwrap();
+
+    let db = rusqlite::Connection::open(&db_path).unwrap();
+    let mut statement = db
+        .prepare(
+            "SELECT address_local_id, relation, local_id FROM address_relations ORDER BY rowid",
+        )
+        .unwrap();
+    let rows: Vec<(String, String, String)> = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let rows: Vec<_> = rows
+        .iter()
+        .map(|(address, relation, local_id)| {
+            (address.as_str(), relation.as_str(), local_id.as_str())
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            ("0080200000021745", "parent_address", "0080200000021746"),
+            ("0080200000021745", "building", "0080010000021746"),
+            ("0080200000021746", "building", "0080010000021747"),
+            ("0080200000021746", "building", "0080100000300394"),
+        ]
+    );
+    drop(statement);
+    drop(db);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn validates_parent_addresses_but_not_buildings() {
+    // Without the parent address, the reference to it is left dangling
+    let document = fixture("bag.gml");
+    let start = document.rfind("<gml:featureMember>").unwrap();
+    let end = document.rfind("</gml:featureMember>").unwrap() + "</gml:featureMember>".len();
+    let document = format!("{}{}", &document[..start], &document[end..]);
+
+    let emitter = Parser::new(document.as_bytes())
+        .parse(ValidationEmitter::new())
+        .unwrap();
+    let report = emitter.report().unwrap();
+    assert_eq!(report.dangling_references.len(), 1);
+    let dangling = &report.dangling_references[0];
+    assert_eq!(dangling.kind, FeatureKind::Address);
+    assert_eq!(dangling.local_id, "0080200000021746");
+    assert_eq!(dangling.first_referrer.field, "parent_address_ref");
+}
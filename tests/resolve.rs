@@ -0,0 +1,126 @@
+mod common;
+
+use std::path::Path;
+
+use common::{address, admin_unit, document, fixture, temp_dir, thoroughfare};
+use gmlparser::Parser;
+use gmlparser::emitter::{FeatureMemberEmitter, resolved::ResolvedTsvEmitter};
+use gmlparser::resolve::{ResolvedAddress, ResolvedAddressEmitter, ResolvingEmitter};
+
+#[derive(Default)]
+struct Collect(Vec<ResolvedAddress>);
+
+impl ResolvedAddressEmitter for Collect {
+    fn emit(&mut self, address: ResolvedAddress) -> gmlparser::Result<()> {
+        self.0.push(address);
+        Ok(())
+    }
+}
+
+fn files(dir: &Path) -> usize {
+    std::fs::read_dir(dir).unwrap().count()
+}
+
+#[test]
+fn resolves_addresses_after_their_names_in_order() {
+    let spill_dir = temp_dir("resolve-order");
+    let mut emitter = ResolvingEmitter::new(Collect::default()).with_spill_dir(&spill_dir);
+    emitter.start().unwrap();
+    for feature_member in Parser::new(fixture("bag.gml").as_bytes()) {
+        emitter.emit(feature_member.unwrap()).unwrap();
+    }
+    // Nothing had to be spilled
+    assert_eq!(files(&spill_dir), 0);
+    emitter.end().unwrap();
+
+    let resolved = |local_id: &str, number: &str, number_extension: Option<&str>| ResolvedAddress {
+        local_id: local_id.to_string(),
+        street: Some("Oldehoofsterkerkhof".to_string()),
+        number: Some(number.to_string()),
+        number_extension: number_extension.map(str::to_string),
+        number_2nd_extension: None,
+        postcode: Some("8911DJ".to_string()),
+        city: Some("Leeuwarden".to_string()),
+        municipality: Some("Leeuwarden".to_string()),
+    };
+    assert_eq!(
+        emitter.into_inner().0,
+        [
+            resolved("0080200000021745", "2", Some("A")),
+            // The post code of the postal descriptor, as the address has no postal delivery
+            // identifier
+            resolved("0080200000021746", "4", None),
+        ]
+    );
+}
+
+#[test]
+fn spills_addresses_before_their_names() {
+    let spill_dir = temp_dir("resolve-spill");
+    // The gml:ids don't name the types, so only the features themselves tell them
+    let document = document(&[
+        thoroughfare("s1", "0003300000116985", "Dijkstraat"),
+        address("0001", &["#s1", "#s2", "#m1"]),
+        address("0002", &["#s1"]),
+        thoroughfare("s2", "0003300000117000", "Kerkstraat"),
+        admin_unit("m1", "0003", "Appingedam"),
+    ]);
+
+    let emitter = Parser::new(document.as_bytes())
+        .parse(ResolvingEmitter::new(Collect::default()).with_spill_dir(&spill_dir))
+        .unwrap();
+    let addresses = emitter.into_inner().0;
+
+    // The address with forward references is only emitted at the end
+    let resolved: Vec<_> = addresses
+        .iter()
+        .map(|address| {
+            (
+                address.local_id.as_str(),
+                address.street.as_deref(),
+                address.municipality.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        resolved,
+        [
+            ("0002", Some("Dijkstraat"), None),
+            ("0001", Some("Dijkstraat"), Some("Appingedam")),
+        ]
+    );
+    assert_eq!(files(&spill_dir), 0);
+}
+
+#[test]
+fn removes_the_spill_file_when_dropped() {
+    let spill_dir = temp_dir("resolve-drop");
+    let mut emitter = ResolvingEmitter::new(Collect::default()).with_spill_dir(&spill_dir);
+    emitter.start().unwrap();
+    for feature_member in Parser::new(document(&[address("0001", &["#s1"])]).as_bytes()) {
+        emitter.emit(feature_member.unwrap()).unwrap();
+    }
+    assert_eq!(files(&spill_dir), 1);
+
+    // As when parsing fails before the end
+    drop(emitter);
+    assert_eq!(files(&spill_dir), 0);
+}
+
+#[test]
+fn escapes_tabs_and_line_breaks_in_tsv() {
+    let output_dir = temp_dir("resolve-tsv");
+    let document = document(&[
+        thoroughfare("s1", "0003300000116985", "Dijk&#9;straat&#10;Noord\\Zuid"),
+        address("0001", &["#s1"]),
+    ]);
+    Parser::new(document.as_bytes())
+        .parse(ResolvingEmitter::new(
+            ResolvedTsvEmitter::new(&output_dir).unwrap(),
+        ))
+        .unwrap();
+
+    let tsv = std::fs::read_to_string(output_dir.join("resolved_addresses.tsv")).unwrap();
+    let rows: Vec<_> = tsv.lines().skip(1).collect();
+    assert_eq!(rows, ["0001\tDijk\\tstraat\\nNoord\\\\Zuid\t\t\t\t\t\t"]);
+}
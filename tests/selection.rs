@@ -0,0 +1,122 @@
+mod common;
+
+use common::{address, describe, document, fixture, local_id, parse, thoroughfare};
+use gmlparser::parser::{FeatureMemberType, Field};
+use gmlparser::{FeatureMember, Mode, Parser, Selection};
+
+fn parse_selected(document: &str, selection: Selection) -> Vec<FeatureMember<'static>> {
+    Parser::new(document.as_bytes())
+        .with_selection(selection)
+        .into_iter()
+        .collect::<gmlparser::Result<_>>()
+        .unwrap()
+}
+
+fn descriptions(feature_members: &[FeatureMember<'_>]) -> Vec<String> {
+    feature_members.iter().map(describe).collect()
+}
+
+#[test]
+fn reads_only_the_selected_feature_types() {
+    let document = fixture("bag.gml");
+    let all = descriptions(&parse(&document));
+
+    let names = parse_selected(
+        &document,
+        Selection::all().with_feature_types([
+            FeatureMemberType::ThoroughfareName,
+            FeatureMemberType::AddressAreaName,
+        ]),
+    );
+    assert_eq!(descriptions(&names), all[1..3]);
+
+    // The names the addresses refer to are skipped, but can be told from their identifiers
+    let addresses = parse_selected(
+        &document,
+        Selection::all().with_feature_types([FeatureMemberType::Address]),
+    );
+    assert_eq!(descriptions(&addresses), all[4..]);
+}
+
+#[test]
+fn doesnt_count_unselected_feature_members_as_skipped() {
+    let document = document(&[
+        thoroughfare("t1", "0003300000116985", "Dijkstraat"),
+        "<ad:Foo/>".to_string(),
+        address("0001", &[]),
+    ]);
+    let mut feature_members = Parser::new(document.as_bytes())
+        .with_mode(Mode::Lenient)
+        .with_selection(Selection::all().with_feature_types([FeatureMemberType::Address]))
+        .into_iter();
+    let local_ids: Vec<_> = (&mut feature_members)
+        .map(|feature_member| local_id(&feature_member.unwrap()))
+        .collect();
+    assert_eq!(local_ids, ["0001"]);
+    assert_eq!(
+        feature_members.skipped().keys().collect::<Vec<_>>(),
+        ["{http://inspire.ec.europa.eu/schemas/ad/4.0}Foo"]
+    );
+}
+
+#[test]
+fn leaves_out_skipped_fields() {
+    let document = fixture("bag.gml");
+    let all = parse(&document);
+    let feature_members = parse_selected(
+        &document,
+        Selection::all()
+            .without(Field::Position)
+            .without(Field::Locators)
+            .without(Field::Names),
+    );
+    assert_eq!(feature_members.len(), all.len());
+
+    for feature_member in &feature_members {
+        match feature_member {
+            FeatureMember::Address {
+                number,
+                locators,
+                position,
+                thoroughfare_ref,
+                ..
+            } => {
+                assert!(number.is_none());
+                assert!(locators.is_empty());
+                assert!(position.is_none());
+                assert!(thoroughfare_ref.is_some());
+            }
+            FeatureMember::AdminUnitName { name, names, .. }
+            | FeatureMember::AddressAreaName { name, names, .. }
+            | FeatureMember::ThoroughfareName { name, names, .. } => {
+                assert!(name.is_none());
+                assert!(names.is_empty());
+            }
+            FeatureMember::PostalDescriptor {
+                post_code,
+                post_name,
+                ..
+            } => {
+                assert_eq!(post_code.as_deref(), Some("8911DJ"));
+                assert!(post_name.is_none());
+            }
+        }
+    }
+
+    // Everything else is read as before
+    let local_ids: Vec<_> = feature_members.iter().map(local_id).collect();
+    let all_local_ids: Vec<_> = all.iter().map(local_id).collect();
+    assert_eq!(local_ids, all_local_ids);
+}
+
+#[test]
+fn parses_types_and_fields_as_given_on_the_command_line() {
+    assert_eq!("ad:Address".parse(), Ok(FeatureMemberType::Address));
+    assert_eq!(
+        "ThoroughfareName".parse(),
+        Ok(FeatureMemberType::ThoroughfareName)
+    );
+    assert!("ad:Building".parse::<FeatureMemberType>().is_err());
+    assert_eq!("names".parse(), Ok(Field::Names));
+    assert!("buildings".parse::<Field>().is_err());
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::{address, admin_unit, document, temp_dir, thoroughfare};
+use gmlparser::Parser;
+use gmlparser::crs::CoordinateOutput;
+use gmlparser::emitter::sqlite::SqliteEmitter;
+
+fn address_columns(db_path: &std::path::Path) -> Vec<String> {
+    let db = rusqlite::Connection::open(db_path).unwrap();
+    let mut statement = db
+        .prepare("SELECT name FROM pragma_table_info('addresses')")
+        .unwrap();
+    statement
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+#[test]
+fn recreates_tables_with_other_coordinate_columns() {
+    let db_path = temp_dir("sqlite-recreate").join("nl-addresses.sqlite");
+    let document = document(&[address("1", &[])]);
+
+    for coordinate_output in [CoordinateOutput::Rd, CoordinateOutput::Wgs84] {
+        let emitter = SqliteEmitter::new(&db_path)
+            .unwrap()
+            .with_coordinate_output(coordinate_output);
+        emitter.create_tables().unwrap();
+        Parser::new(document.as_bytes()).parse(emitter).unwrap();
+    }
+
+    let columns = address_columns(&db_path);
+    assert!(
+        columns.iter().any(|column| column == "lat"),
+        "{:?}",
+        columns
+    );
+    assert!(!columns.iter().any(|column| column == "x"), "{:?}", columns);
+}
+
+#[test]
+fn replaces_tables_of_an_older_schema() {
+    let db_path = temp_dir("sqlite-older").join("nl-addresses.sqlite");
+    rusqlite::Connection::open(&db_path)
+        .unwrap()
+        .execute_batch(
+            "CREATE TABLE addresses (local_id INTEGER PRIMARY KEY, number TEXT);
+            CREATE TABLE names (local_id TEXT NOT NULL, spelling TEXT NOT NULL);",
+        )
+        .unwrap();
+
+    let emitter = SqliteEmitter::new(&db_path).unwrap();
+    emitter.create_tables().unwrap();
+    Parser::new(document(&[address("1", &[])]).as_bytes())
+        .parse(emitter)
+        .unwrap();
+
+    let columns = address_columns(&db_path);
+    assert!(
+        columns.iter().any(|column| column == "gml_id"),
+        "{:?}",
+        columns
+    );
+}
+
+#[test]
+fn keeps_local_ids_as_text() {
+    let dir = temp_dir("sqlite-local-ids");
+    let db_path = dir.join("nl-addresses.sqlite");
+    let document = document(&[
+        admin_unit("g1", "0080", "Leeuwarden"),
+        thoroughfare("t1", "be-uuid-abc", "Dijkstraat"),
+        address("0080200000021745", &["#g1", "#t1"]),
+        address("be-uuid-def", &[]),
+    ]);
+    let emitter = SqliteEmitter::new(&db_path).unwrap();
+    emitter.create_tables().unwrap();
+    Parser::new(document.as_bytes()).parse(emitter).unwrap();
+
+    let db = rusqlite::Connection::open(&db_path).unwrap();
+    let local_ids = |table: &str| -> Vec<String> {
+        db.prepare(&format!("SELECT local_id FROM {} ORDER BY rowid", table))
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    };
+    assert_eq!(local_ids("admin_units"), ["0080"]);
+    assert_eq!(local_ids("thoroughfares"), ["be-uuid-abc"]);
+    assert_eq!(local_ids("addresses"), ["0080200000021745", "be-uuid-def"]);
+    drop(db);
+    std::fs::remove_dir_all(dir).unwrap();
+}
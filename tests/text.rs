@@ -0,0 +1,83 @@
+mod common;
+
+use common::{document, fixture, parse, thoroughfare};
+use gmlparser::{Error, FeatureMember, Parser};
+
+/// Returns the name of every thoroughfare in `document`.
+fn thoroughfare_names(document: &str) -> Vec<String> {
+    parse(document)
+        .iter()
+        .filter_map(|feature_member| match feature_member {
+            FeatureMember::ThoroughfareName { name, .. } => name.as_deref().map(str::to_string),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn unescapes_entities_and_character_references() {
+    let escaped = fixture("bag.gml").replace(
+        "<gn:text>Oldehoofsterkerkhof</gn:text>",
+        "<gn:text>&#39;t Oldehoofster&#x2D;kerkhof &amp; &lt;Hofplein&gt;</gn:text>",
+    );
+    assert_eq!(
+        thoroughfare_names(&escaped),
+        ["'t Oldehoofster-kerkhof & <Hofplein>"]
+    );
+
+    let document = document(&[thoroughfare(
+        "t1",
+        "0518300000000001",
+        "&apos;s-Gravenhage &quot;Centrum&quot;",
+    )]);
+    assert_eq!(thoroughfare_names(&document), ["'s-Gravenhage \"Centrum\""]);
+}
+
+#[test]
+fn reads_cdata_as_text() {
+    let document = document(&[
+        thoroughfare("t1", "1", "<![CDATA[Koning & <Co>]]>"),
+        thoroughfare("t2", "2", "<![CDATA[]]>Dijkstraat"),
+    ]);
+    assert_eq!(
+        thoroughfare_names(&document),
+        ["Koning & <Co>", "Dijkstraat"]
+    );
+}
+
+#[test]
+fn joins_text_split_by_comments_and_cdata() {
+    let document = document(&[
+        thoroughfare("t1", "1", "Dijk<!-- a comment -->straat"),
+        thoroughfare("t2", "2", "Koning <![CDATA[&]]> Co"),
+        thoroughfare(
+            "t3",
+            "3",
+            "<!-- before -->&#39;s-<![CDATA[Graven]]>hage<!-- after -->",
+        ),
+    ]);
+    assert_eq!(
+        thoroughfare_names(&document),
+        ["Dijkstraat", "Koning & Co", "'s-Gravenhage"]
+    );
+
+    // Local IDs are joined as well
+    let document = fixture("bag.gml").replace(
+        "<base:localId>0080300000000112</base:localId>",
+        "<base:localId>00803<!-- -->00000000112</base:localId>",
+    );
+    let feature_members = parse(&document);
+    let FeatureMember::ThoroughfareName { local_id, .. } = &feature_members[2] else {
+        panic!("expected a thoroughfare");
+    };
+    assert_eq!(local_id, "0080300000000112");
+}
+
+#[test]
+fn fails_on_unknown_entities() {
+    let document = document(&[thoroughfare("t1", "1", "Koning&nbsp;Co")]);
+    let result = Parser::new(document.as_bytes())
+        .into_iter()
+        .collect::<gmlparser::Result<Vec<_>>>();
+    assert!(matches!(result, Err(Error::Xml { .. })));
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use common::{address, admin_unit, document, fixture, thoroughfare};
+use gmlparser::Parser;
+use gmlparser::emitter::validation::{
+    DanglingReference, Duplicate, FeatureKind, Referrer, ValidationEmitter, ValidationReport,
+};
+
+fn validate(document: &str) -> ValidationReport {
+    let emitter = Parser::new(document.as_bytes())
+        .parse(ValidationEmitter::new())
+        .unwrap();
+    emitter.report().unwrap().clone()
+}
+
+/// A document with an address referring to a missing thoroughfare, an admin unit that no
+/// address refers to, and an address and a thoroughfare that are there twice.
+fn invalid_document() -> String {
+    document(&[
+        admin_unit("g1", "0363", "Amsterdam"),
+        admin_unit("g2", "0599", "Rotterdam"),
+        thoroughfare("t1", "0363300000000001", "Dijkstraat"),
+        thoroughfare("t2", "0363300000000001", "Dijkstraat"),
+        address("0001", &["#g1", "#t1"]),
+        address(
+            "0002",
+            &["#g1", "#nl-imbag-ad-thoroughfarename.0363300000000002"],
+        ),
+        address("0003", &["#nl-imbag-ad-thoroughfarename.0363300000000002"]),
+        address("0001", &["#g1", "#t1"]),
+    ])
+}
+
+#[test]
+fn finds_nothing_wrong_with_the_fixture() {
+    let report = validate(&fixture("bag.gml"));
+    assert!(report.is_valid());
+    assert_eq!(
+        report.features.into_iter().collect::<Vec<_>>(),
+        [
+            (FeatureKind::Address, 2),
+            (FeatureKind::AdminUnit, 1),
+            (FeatureKind::AddressArea, 1),
+            (FeatureKind::Thoroughfare, 1),
+            (FeatureKind::PostalDescriptor, 1),
+        ]
+    );
+}
+
+#[test]
+fn finds_dangling_references() {
+    let report = validate(&invalid_document());
+    assert!(!report.is_valid());
+    assert_eq!(
+        report.dangling_references,
+        [DanglingReference {
+            kind: FeatureKind::Thoroughfare,
+            local_id: "0363300000000002".to_string(),
+            references: 2,
+            first_referrer: Referrer {
+                kind: FeatureKind::Address,
+                local_id: "0002".to_string(),
+                field: "thoroughfare_ref",
+            },
+        }]
+    );
+}
+
+#[test]
+fn finds_names_that_no_address_refers_to() {
+    let report = validate(&invalid_document());
+    assert_eq!(
+        report.orphans,
+        [(FeatureKind::AdminUnit, "0599".to_string())]
+    );
+
+    // A reference from another name doesn't count
+    let document = fixture("bag.gml").replace(
+        r##"<ad:component xlink:href="#nl-imbag-ad-adminunitname.0080"/>"##,
+        "",
+    );
+    let report = validate(&document);
+    assert_eq!(
+        report.orphans,
+        [(FeatureKind::AdminUnit, "0080".to_string())]
+    );
+}
+
+#[test]
+fn finds_duplicate_local_ids() {
+    let report = validate(&invalid_document());
+    assert_eq!(
+        report.duplicates,
+        [
+            Duplicate {
+                kind: FeatureKind::Address,
+                local_id: "0001".to_string(),
+                count: 2,
+            },
+            Duplicate {
+                kind: FeatureKind::Thoroughfare,
+                local_id: "0363300000000001".to_string(),
+                count: 2,
+            },
+        ]
+    );
+    assert_eq!(report.features[&FeatureKind::Address], 4);
+}
+
+#[test]
+fn writes_the_report_as_json() {
+    let mut json = Vec::new();
+    validate(&invalid_document()).write_json(&mut json).unwrap();
+    assert_eq!(
+        String::from_utf8(json).unwrap(),
+        concat!(
+            r#"{"valid":false,"features":{"address":4,"admin_unit":2,"thoroughfare":2},"#,
+            r#""dangling_references":[{"type":"thoroughfare","local_id":"0363300000000002","references":2,"first_referrer":{"type":"address","local_id":"0002","field":"thoroughfare_ref"}}],"#,
+            r#""orphans":[{"type":"admin_unit","local_id":"0599"}],"#,
+            r#""duplicates":[{"type":"address","local_id":"0001","count":2},{"type":"thoroughfare","local_id":"0363300000000001","count":2}]}"#,
+            "\n"
+        )
+    );
+}
@@ -0,0 +1,255 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::{NAMESPACES, Response, address, local_id, serve, thoroughfare};
+
+use gmlparser::wfs::GetFeature;
+use gmlparser::{Error, FeatureMember};
+
+/// A `wfs:FeatureCollection` with a `wfs:member` per feature.
+fn page(features: &[String], next: Option<&str>, number_returned: bool) -> String {
+    let mut attributes = String::new();
+    if let Some(next) = next {
+        attributes.push_str(&format!(r#" next="{}""#, next.replace('&', "&amp;")));
+    }
+    if number_returned {
+        attributes.push_str(&format!(r#" numberReturned="{}""#, features.len()));
+    }
+    let members: String = features
+        .iter()
+        .map(|feature| format!("\n  <wfs:member>{}</wfs:member>", feature))
+        .collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<wfs:FeatureCollection {}{} numberMatched=\"unknown\" timeStamp=\"2024-05-01T12:00:00Z\">{}\n</wfs:FeatureCollection>",
+        NAMESPACES, attributes, members
+    )
+}
+
+const EXCEPTION_REPORT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ows:ExceptionReport xmlns:ows="http://www.opengis.net/ows/1.1" version="2.0.0">
+  <ows:Exception exceptionCode="InvalidParameterValue" locator="typeNames">
+    <ows:ExceptionText>Unknown feature type ad:Adres</ows:ExceptionText>
+  </ows:Exception>
+</ows:ExceptionReport>"#;
+
+fn ok(body: String) -> Response {
+    Response::ok("text/xml; subtype=gml/3.2", body)
+}
+
+/// Returns the value of `parameter` in the query of `target`, still URL-encoded.
+fn query_parameter<'a>(target: &'a str, parameter: &str) -> Option<&'a str> {
+    let (_, query) = target.split_once('?')?;
+    query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix(parameter)?.strip_prefix('='))
+}
+
+#[test]
+fn follows_next_links() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let base_url = serve(
+        |base_url, target| {
+            if target.contains("page=2") {
+                // The street on the first page is only known by its gml:id
+                ok(page(&[address("0001", &["#t1"])], None, true))
+            } else {
+                let next = format!("{}/wfs?page=2&STARTINDEX=1", base_url);
+                ok(page(
+                    &[thoroughfare("t1", "0003300000116985", "Dijkstraat")],
+                    Some(&next),
+                    true,
+                ))
+            }
+        },
+        requests.clone(),
+    );
+
+    let mut get_feature = GetFeature::new(
+        format!("{}/wfs", base_url),
+        ["ad:ThoroughfareName", "ad:Address"],
+    )
+    .with_count(1)
+    .with_filter("<fes:Filter/>");
+    let mut feature_members = Vec::new();
+    get_feature
+        .for_each(|feature_member| {
+            feature_members.push(feature_member);
+            Ok(())
+        })
+        .unwrap();
+
+    let local_ids: Vec<_> = feature_members.iter().map(local_id).collect();
+    assert_eq!(local_ids, ["0003300000116985", "0001"]);
+    let FeatureMember::Address {
+        thoroughfare_ref: Some(thoroughfare_ref),
+        ..
+    } = &feature_members[1]
+    else {
+        panic!("expected an address with a thoroughfare");
+    };
+    assert_eq!(thoroughfare_ref.href, "#t1");
+    assert_eq!(thoroughfare_ref.local_id, "0003300000116985");
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    // A query per type, each with the filter
+    assert_eq!(
+        query_parameter(&requests[0], "TYPENAMES"),
+        Some("%28ad%3AThoroughfareName%29%28ad%3AAddress%29")
+    );
+    assert_eq!(
+        query_parameter(&requests[0], "FILTER"),
+        Some("%28%3Cfes%3AFilter%2F%3E%29%28%3Cfes%3AFilter%2F%3E%29")
+    );
+    assert_eq!(query_parameter(&requests[0], "COUNT"), Some("1"));
+    assert_eq!(requests[1], "/wfs?page=2&STARTINDEX=1");
+}
+
+#[test]
+fn pages_by_start_index_without_next_links() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let base_url = serve(
+        |_, target| {
+            let start_index: usize = query_parameter(target, "STARTINDEX")
+                .and_then(|start_index| start_index.parse().ok())
+                .unwrap_or_default();
+            let features: Vec<_> = (start_index..5)
+                .take(2)
+                .map(|index| thoroughfare(&format!("t{}", index), &index.to_string(), "Straat"))
+                .collect();
+            // Only some services say how many features a page has
+            ok(page(&features, None, start_index == 0))
+        },
+        requests.clone(),
+    );
+
+    let mut get_feature =
+        GetFeature::new(format!("{}/wfs?MAP=ad", base_url), ["ad:ThoroughfareName"])
+            .with_count(2)
+            .with_bbox(
+                "120000,480000,125000,490000,urn:ogc:def:crs:EPSG::28992"
+                    .parse()
+                    .unwrap(),
+            )
+            .with_srs_name("urn:ogc:def:crs:EPSG::28992");
+    let mut local_ids = Vec::new();
+    get_feature
+        .for_each(|feature_member| {
+            local_ids.push(local_id(&feature_member));
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(local_ids, ["0", "1", "2", "3", "4"]);
+
+    let requests = requests.lock().unwrap();
+    let start_indexes: Vec<_> = requests
+        .iter()
+        .map(|target| query_parameter(target, "STARTINDEX").unwrap())
+        .collect();
+    // Without the number of matching features, only an empty page ends the response
+    assert_eq!(start_indexes, ["0", "2", "4", "5"]);
+    for target in requests.iter() {
+        assert_eq!(query_parameter(target, "MAP"), Some("ad"));
+        assert_eq!(
+            query_parameter(target, "TYPENAMES"),
+            Some("ad%3AThoroughfareName")
+        );
+        assert_eq!(
+            query_parameter(target, "BBOX"),
+            Some("120000%2C480000%2C125000%2C490000%2Curn%3Aogc%3Adef%3Acrs%3AEPSG%3A%3A28992")
+        );
+        assert_eq!(
+            query_parameter(target, "SRSNAME"),
+            Some("urn%3Aogc%3Adef%3Acrs%3AEPSG%3A%3A28992")
+        );
+    }
+}
+
+#[test]
+fn pages_on_when_the_service_caps_the_count() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let base_url = serve(
+        |_, target| {
+            let start_index: usize = query_parameter(target, "STARTINDEX")
+                .and_then(|start_index| start_index.parse().ok())
+                .unwrap_or_default();
+            // At most 2 features per page, whatever the count
+            let features: Vec<_> = (start_index..5)
+                .take(2)
+                .map(|index| thoroughfare(&format!("t{}", index), &index.to_string(), "Straat"))
+                .collect();
+            ok(page(&features, None, true)
+                .replace(r#"numberMatched="unknown""#, r#"numberMatched="5""#))
+        },
+        requests.clone(),
+    );
+
+    let mut get_feature =
+        GetFeature::new(format!("{}/wfs", base_url), ["ad:ThoroughfareName"]).with_count(10);
+    let mut local_ids = Vec::new();
+    get_feature
+        .for_each(|feature_member| {
+            local_ids.push(local_id(&feature_member));
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(local_ids, ["0", "1", "2", "3", "4"]);
+
+    let requests = requests.lock().unwrap();
+    let start_indexes: Vec<_> = requests
+        .iter()
+        .map(|target| query_parameter(target, "STARTINDEX").unwrap())
+        .collect();
+    assert_eq!(start_indexes, ["0", "2", "4"]);
+}
+
+#[test]
+fn stops_at_an_empty_page() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let base_url = serve(
+        |base_url, _| ok(page(&[], Some(&format!("{}/wfs", base_url)), true)),
+        requests.clone(),
+    );
+
+    let mut get_feature = GetFeature::new(format!("{}/wfs", base_url), ["ad:Address"]);
+    get_feature
+        .for_each(|_| panic!("expected no features"))
+        .unwrap();
+    assert_eq!(requests.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn reports_exceptions() {
+    let base_url = serve(
+        |_, target| {
+            let status = if target.starts_with("/error") {
+                400
+            } else {
+                200
+            };
+            Response {
+                status,
+                content_type: "application/xml",
+                body: EXCEPTION_REPORT.into(),
+            }
+        },
+        Arc::default(),
+    );
+
+    for path in ["/error", "/ok"] {
+        let url = format!("{}{}", base_url, path);
+        let error = GetFeature::new(&url, ["ad:Adres"])
+            .for_each(|_| Ok(()))
+            .unwrap_err();
+        let Error::Service {
+            url: error_url,
+            message,
+        } = error
+        else {
+            panic!("expected a service error, got {}", error);
+        };
+        assert!(error_url.starts_with(&url), "{}", error_url);
+        assert_eq!(message, "Unknown feature type ad:Adres");
+    }
+}